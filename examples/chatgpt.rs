@@ -1,5 +1,6 @@
 use fieri::{
-    chat::{chat, ChatMessageBuilder, ChatParamBuilder},
+    chat::chat,
+    types::{ChatMessageBuilder, ChatParamBuilder},
     Client, Error,
 };
 
@@ -1,7 +1,6 @@
-use fieri::{
-    completion::{create, CompletionParamBuilder},
-    Client, Error,
-};
+#![allow(deprecated)]
+
+use fieri::{completion::create, types::CompletionParamBuilder, Client, Error};
 
 #[tokio::main]
 async fn main() -> Result<(), Error> {
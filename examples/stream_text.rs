@@ -1,7 +1,10 @@
-//! Create a completion stream for the provided prompt and parameters.
+//! Stream a chat completion, printing the generated text as it arrives.
+
+use futures::StreamExt;
 
 use fieri::{
-    completion::{create_with_stream, Completion, CompletionParamBuilder},
+    chat::chat_stream,
+    types::{ChatMessageBuilder, ChatParamBuilder},
     Client, Error,
 };
 
@@ -9,21 +12,19 @@ use fieri::{
 async fn main() -> Result<(), Error> {
     let client = Client::new();
 
-    let param = CompletionParamBuilder::new("ada")
-        .prompt("unnecessarily lo")
+    let message = ChatMessageBuilder::new("user", "Write a haiku about the sea.").build()?;
+    let param = ChatParamBuilder::new("gpt-3.5-turbo", vec![message])
         .temperature(0.5)
         .build()?;
 
-    let mut resp = create_with_stream(&client, &param).await?;
+    let mut stream = chat_stream(&client, &param).await?;
 
-    while let Some(chunk) = resp.chunk().await? {
-        if chunk.to_vec() == b"data: [DONE]\n\n" {
-            break;
+    while let Some(chunk) = stream.next().await {
+        for choice in chunk?.choices {
+            print!("{}", choice.delta.content.unwrap_or_default());
         }
-
-        let v: Completion = serde_json::from_slice(&chunk[5..])?;
-        v.choices.iter().for_each(|c| println!("{:?}", c.text));
     }
+    println!();
 
     Ok(())
 }
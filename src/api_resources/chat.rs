@@ -1,18 +1,471 @@
+use futures::stream::BoxStream;
+#[cfg(feature = "schema")]
+use serde::de::DeserializeOwned;
+
+#[cfg(feature = "schema")]
+use crate::types::{ChatMessage, ChatRole, ResponseFormat};
 use crate::{
-    types::{Chat, ChatParam},
-    Client, Result,
+    tools::{ToolRegistry, ToolRun},
+    types::{Chat, ChatChunk, ChatParam},
+    Client, Error, RequestOptions, Result,
 };
 
 pub async fn chat(client: &Client, param: &ChatParam) -> Result<Chat> {
     client.chat(param).await
 }
 
+/// Creates a chat completion, streaming back partial message deltas as they're generated.
+///
+/// The `stream` field of the given parameters is ignored, as it's always enabled.
+///
+/// Related OpenAI docs: [Create Chat Completion](https://platform.openai.com/docs/api-reference/chat/create#chat-create-stream)
+///
+/// ## Example
+/// ```no_run
+/// use futures::StreamExt;
+/// use fieri::{
+///     chat::chat_stream,
+///     types::{ChatMessageBuilder, ChatParamBuilder},
+///     Client,
+/// };
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let client = Client::new();
+///
+///     let message = ChatMessageBuilder::new("user", "Hello!").build()?;
+///     let param = ChatParamBuilder::new("gpt-3.5-turbo", vec![message]).build()?;
+///
+///     let mut stream = chat_stream(&client, &param).await?;
+///
+///     while let Some(chunk) = stream.next().await {
+///         for choice in chunk?.choices {
+///             print!("{}", choice.delta.content.unwrap_or_default());
+///         }
+///     }
+///
+///     Ok(())
+/// }
+/// ```
+pub async fn chat_stream(
+    client: &Client,
+    param: &ChatParam,
+) -> Result<BoxStream<'static, Result<ChatChunk>>> {
+    client.chat_stream(param).await
+}
+
+/// Like [`chat`], with the given [`RequestOptions`].
+pub async fn chat_with(
+    client: &Client,
+    param: &ChatParam,
+    options: RequestOptions,
+) -> Result<Chat> {
+    chat(&client.with_options(options)?, param).await
+}
+
+/// Like [`chat_stream`], with the given [`RequestOptions`].
+pub async fn chat_stream_with(
+    client: &Client,
+    param: &ChatParam,
+    options: RequestOptions,
+) -> Result<BoxStream<'static, Result<ChatChunk>>> {
+    chat_stream(&client.with_options(options)?, param).await
+}
+
+/// Creates chat completions until the model replies without calling tools, answering its calls with the given registry.
+///
+/// The tools of the registry replace any `tools` of the given parameters.
+/// Each time the model calls tools, the matching functions run concurrently, and their results are sent back.
+/// Fails with [`Error::ToolIterationsExceeded`] when `max_iterations` chats have been sent without a final reply.
+///
+/// See [`tools`](crate::tools) for an example.
+pub async fn run_with_tools(
+    client: &Client,
+    param: &ChatParam,
+    registry: &ToolRegistry,
+    max_iterations: usize,
+) -> Result<ToolRun> {
+    let mut param = ChatParam {
+        tools: Some(registry.tools().to_vec()),
+        ..param.clone()
+    };
+
+    for iteration in 1..=max_iterations {
+        let chat = client.chat(&param).await?;
+        let Some(choice) = chat.choices.first() else {
+            return Ok(ToolRun {
+                messages: param.messages,
+                chat,
+                iterations: iteration,
+            });
+        };
+
+        let calls = choice.message.tool_calls.clone().unwrap_or_default();
+        // Fields fieri doesn't model, like `annotations`, are rejected when sent back.
+        let mut message = choice.message.clone();
+        message.extra.clear();
+        param.messages.push(message);
+        if calls.is_empty() {
+            return Ok(ToolRun {
+                messages: param.messages,
+                chat,
+                iterations: iteration,
+            });
+        }
+
+        param.messages.extend(registry.call_all(&calls).await);
+    }
+
+    Err(Error::ToolIterationsExceeded(max_iterations))
+}
+
+/// Creates a chat completion replying in JSON matching the schema of `T`, deserialized into `T`.
+///
+/// The `response_format` of the given parameters is replaced by [`ResponseFormat::json_schema_for`].
+/// Fails with [`Error::Refusal`] when the model refuses to reply,
+/// and with [`Error::InvalidStructuredOutput`] when its reply doesn't deserialize into `T`.
+///
+/// Only available with the `schema` feature.
+///
+/// ## Example
+/// ```no_run
+/// use fieri::{
+///     chat::chat_typed,
+///     types::{ChatMessageBuilder, ChatParamBuilder},
+///     Client,
+/// };
+/// use schemars::JsonSchema;
+/// use serde::Deserialize;
+///
+/// #[derive(Debug, Deserialize, JsonSchema)]
+/// struct Event {
+///     name: String,
+///     date: String,
+///     participants: Vec<String>,
+/// }
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let client = Client::new();
+///
+///     let message = ChatMessageBuilder::new("user", "Alice and Bob are going to a science fair on Friday.").build()?;
+///     let param = ChatParamBuilder::new("gpt-4o", vec![message]).build()?;
+///
+///     let event: Event = chat_typed(&client, &param).await?;
+///     println!("{:#?}", event);
+///
+///     Ok(())
+/// }
+/// ```
+#[cfg(feature = "schema")]
+pub async fn chat_typed<T>(client: &Client, param: &ChatParam) -> Result<T>
+where
+    T: schemars::JsonSchema + DeserializeOwned,
+{
+    chat_typed_reasking(client, param, 0).await
+}
+
+/// Like [`chat_typed`], asking the model up to `max_reasks` more times when its reply doesn't deserialize into `T`.
+///
+/// Each invalid reply is kept in the conversation, followed by the error it caused. Refusals aren't asked again.
+#[cfg(feature = "schema")]
+pub async fn chat_typed_reasking<T>(
+    client: &Client,
+    param: &ChatParam,
+    max_reasks: usize,
+) -> Result<T>
+where
+    T: schemars::JsonSchema + DeserializeOwned,
+{
+    let mut param = ChatParam {
+        response_format: Some(ResponseFormat::json_schema_for::<T>()),
+        ..param.clone()
+    };
+
+    let mut reasks = 0;
+    loop {
+        let chat = client.chat(&param).await?;
+        let mut message = chat
+            .choices
+            .into_iter()
+            .next()
+            .map(|c| c.message)
+            .unwrap_or_default();
+        if let Some(refusal) = message.refusal {
+            return Err(Error::Refusal(refusal));
+        }
+
+        let content = message.content.to_text();
+        match serde_json::from_str::<T>(&content) {
+            Ok(value) => return Ok(value),
+            Err(err) if reasks < max_reasks => {
+                log::debug!("Asking again for a valid structured output: {err}");
+                reasks += 1;
+
+                // Fields fieri doesn't model, like `annotations`, are rejected when sent back.
+                message.extra.clear();
+                param.messages.push(message);
+                param.messages.push(ChatMessage {
+                    role: ChatRole::User,
+                    content: format!(
+                        "Your reply doesn't match the requested JSON schema: {err}. Reply again, with only JSON matching the schema."
+                    )
+                    .into(),
+                    ..ChatMessage::default()
+                });
+            }
+            Err(source) => return Err(Error::InvalidStructuredOutput { content, source }),
+        }
+    }
+}
+
 impl Client {
     async fn chat(&self, param: &ChatParam) -> Result<Chat> {
         self.post::<ChatParam, Chat>("chat/completions", Some(param))
             .await
     }
+
+    async fn chat_stream(
+        &self,
+        param: &ChatParam,
+    ) -> Result<BoxStream<'static, Result<ChatChunk>>> {
+        let param = ChatParam {
+            stream: true,
+            ..param.clone()
+        };
+
+        self.post_stream::<ChatParam, ChatChunk>("chat/completions", Some(&param))
+            .await
+    }
 }
 
 #[cfg(test)]
-mod tests {}
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        testing::{Method, MockResponse, MockTransport},
+        types::{ChatMessageBuilder, ChatParamBuilder, ChatRole, ChatStreamAccumulator},
+    };
+
+    fn param() -> ChatParam {
+        let message = ChatMessageBuilder::new("user", "Hello!").build().unwrap();
+
+        ChatParamBuilder::new("gpt-3.5-turbo", vec![message])
+            .build()
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_chat() {
+        let mock = MockTransport::with_fixtures();
+        let resp = chat(&mock.client(), &param()).await.unwrap();
+
+        assert_eq!(
+            resp.choices[0].message.content,
+            "Hello! How can I help you today?"
+        );
+        assert_eq!(resp.usage.total_tokens, 18);
+
+        let request = mock.assert_requested(Method::POST, "chat/completions");
+        assert_eq!(request.json().unwrap()["messages"][0]["content"], "Hello!");
+        assert!(!request.is_stream());
+    }
+
+    #[tokio::test]
+    async fn test_chat_stream() {
+        let mock = MockTransport::with_fixtures();
+        let stream = chat_stream(&mock.client(), &param()).await.unwrap();
+        let resp = ChatStreamAccumulator::collect(stream).await.unwrap();
+
+        assert_eq!(
+            resp.choices[0].message.content,
+            "Hello! How can I help you today?"
+        );
+        assert_eq!(resp.choices[0].finish_reason.as_deref(), Some("stop"));
+        assert_eq!(resp.model, "gpt-3.5-turbo");
+        assert_eq!(resp.system_fingerprint.as_deref(), Some("fp_mock"));
+        assert!(mock.requests()[0].is_stream());
+    }
+
+    fn tool_calls(calls: &[(&str, &str)]) -> MockResponse {
+        let calls: Vec<_> = calls
+            .iter()
+            .map(|(id, arguments)| {
+                json!({
+                    "id": id,
+                    "type": "function",
+                    "function": {"name": "double", "arguments": arguments},
+                })
+            })
+            .collect();
+
+        MockResponse::json(json!({
+            "id": "chatcmpl-tools",
+            "object": "chat.completion",
+            "created": 1699896916,
+            "choices": [{
+                "index": 0,
+                "message": {
+                    "role": "assistant",
+                    "content": null,
+                    "tool_calls": calls,
+                    "annotations": [],
+                },
+                "finish_reason": "tool_calls",
+            }],
+        }))
+    }
+
+    fn registry() -> ToolRegistry {
+        ToolRegistry::new().register(
+            "double",
+            "Doubles a number",
+            json!({"type": "object", "properties": {"n": {"type": "integer"}}}),
+            |args: serde_json::Value| async move {
+                match args["n"].as_i64() {
+                    Some(n) => Ok(n * 2),
+                    None => Err("n must be an integer"),
+                }
+            },
+        )
+    }
+
+    #[tokio::test]
+    async fn test_run_with_tools() {
+        let mock = MockTransport::with_fixtures();
+        mock.once(
+            Method::POST,
+            "chat/completions",
+            tool_calls(&[("call_1", r#"{"n": 2}"#), ("call_2", "not json")]),
+        );
+
+        let run = run_with_tools(&mock.client(), &param(), &registry(), 3)
+            .await
+            .unwrap();
+
+        assert_eq!(run.iterations, 2);
+        assert_eq!(run.messages.len(), 5);
+        assert_eq!(run.messages[1].role, ChatRole::Assistant);
+        assert_eq!(run.messages[2].tool_call_id.as_deref(), Some("call_1"));
+        assert_eq!(run.messages[2].content, "4");
+        assert!(run.messages[3]
+            .content
+            .to_text()
+            .starts_with("Invalid arguments: "));
+        assert_eq!(run.messages[4].content, "Hello! How can I help you today?");
+
+        let requests = mock.requests();
+        let first = requests[0].json().unwrap();
+        let second = requests[1].json().unwrap();
+        assert_eq!(first["tools"][0]["function"]["name"], "double");
+        assert_eq!(second["messages"].as_array().unwrap().len(), 4);
+        assert_eq!(second["messages"][2]["role"], "tool");
+        assert!(second["messages"][1].get("annotations").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_run_with_tools_iterations_exceeded() {
+        let mock = MockTransport::with_fixtures();
+        mock.on(
+            Method::POST,
+            "chat/completions",
+            tool_calls(&[("call_1", r#"{"n": 2}"#)]),
+        );
+
+        let err = run_with_tools(&mock.client(), &param(), &registry(), 2)
+            .await
+            .unwrap_err();
+
+        assert!(matches!(err, Error::ToolIterationsExceeded(2)));
+        assert_eq!(mock.requests().len(), 2);
+    }
+
+    #[cfg(feature = "schema")]
+    #[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+    struct Event {
+        name: String,
+        participants: Vec<String>,
+    }
+
+    #[cfg(feature = "schema")]
+    fn reply(message: serde_json::Value) -> MockResponse {
+        MockResponse::json(json!({
+            "id": "chatcmpl-typed",
+            "object": "chat.completion",
+            "created": 1699896916,
+            "choices": [{"index": 0, "message": message, "finish_reason": "stop"}],
+        }))
+    }
+
+    #[cfg(feature = "schema")]
+    #[tokio::test]
+    async fn test_chat_typed() {
+        let mock = MockTransport::new();
+        mock.once(
+            Method::POST,
+            "chat/completions",
+            reply(json!({
+                "role": "assistant",
+                "content": "{\"name\": \"Science fair\"",
+                "annotations": [],
+            })),
+        )
+        .once(
+            Method::POST,
+            "chat/completions",
+            reply(json!({
+                "role": "assistant",
+                "content": r#"{"name": "Science fair", "participants": ["Alice", "Bob"]}"#,
+            })),
+        );
+
+        let event: Event = chat_typed_reasking(&mock.client(), &param(), 1)
+            .await
+            .unwrap();
+
+        assert_eq!(event.name, "Science fair");
+        assert_eq!(event.participants, ["Alice", "Bob"]);
+
+        let requests = mock.requests();
+        let format = &requests[0].json().unwrap()["response_format"];
+        assert_eq!(format["type"], "json_schema");
+        assert_eq!(format["json_schema"]["name"], "Event");
+        assert_eq!(format["json_schema"]["strict"], true);
+
+        let messages = &requests[1].json().unwrap()["messages"];
+        assert_eq!(messages.as_array().unwrap().len(), 3);
+        assert_eq!(messages[1]["role"], "assistant");
+        assert_eq!(messages[2]["role"], "user");
+        assert!(messages[1].get("annotations").is_none());
+    }
+
+    #[cfg(feature = "schema")]
+    #[tokio::test]
+    async fn test_chat_typed_errors() {
+        let mock = MockTransport::new();
+        mock.once(
+            Method::POST,
+            "chat/completions",
+            reply(
+                json!({"role": "assistant", "content": null, "refusal": "I can't help with that."}),
+            ),
+        )
+        .once(
+            Method::POST,
+            "chat/completions",
+            reply(json!({"role": "assistant", "content": "Sure!"})),
+        );
+        let client = mock.client();
+
+        let err = chat_typed::<Event>(&client, &param()).await.unwrap_err();
+        assert!(matches!(err, Error::Refusal(refusal) if refusal == "I can't help with that."));
+
+        let err = chat_typed::<Event>(&client, &param()).await.unwrap_err();
+        assert!(
+            matches!(err, Error::InvalidStructuredOutput { content, .. } if content == "Sure!")
+        );
+        assert_eq!(mock.requests().len(), 2);
+    }
+}
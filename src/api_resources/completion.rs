@@ -11,14 +11,11 @@
 //!
 //! Showing, not just telling, is often the secret to a good prompt.
 
-use derive_builder::Builder;
-
-use serde::{Deserialize, Serialize};
-use serde_with::skip_serializing_none;
+use futures::stream::BoxStream;
 
 use crate::{
     types::{Completion, CompletionParam},
-    Client, Result,
+    Client, RequestOptions, Result,
 };
 
 /// Creates a completion for the provided prompt and parameters.
@@ -31,7 +28,7 @@ use crate::{
 ///
 /// ## Example
 /// ```no_run
-/// use fieri::{Client, completion::{create, CompletionParamBuilder}};
+/// use fieri::{Client, completion::create, types::CompletionParamBuilder};
 ///
 /// #[tokio::main]
 /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -58,11 +55,15 @@ pub async fn create(client: &Client, param: &CompletionParam) -> Result<Completi
 
 /// Creates a completion stream for the provided prompt and parameters.
 ///
+/// Each item is a partial [`Completion`], containing the text generated since the previous one.
+/// The `stream` field of the given parameters is ignored, as it's always enabled.
+///
 /// Related OpenAI docs: [Create Completions](https://beta.openai.com/docs/api-reference/completions/create#completions/create-stream)
 ///
 /// ## Example
 /// ```no_run
-/// use fieri::{Client, completion::{create_with_stream, CompletionParamBuilder}};
+/// use futures::StreamExt;
+/// use fieri::{Client, completion::create_with_stream, types::CompletionParamBuilder};
 ///
 /// #[tokio::main]
 /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -73,15 +74,17 @@ pub async fn create(client: &Client, param: &CompletionParam) -> Result<Completi
 ///         .temperature(0.5)
 ///         .build()?;
 ///
-///     let mut resp = create_with_stream(&client, &param).await?;
+///     let mut stream = create_with_stream(&client, &param).await?;
 ///
-///     while let Some(chunk) = resp.chunk().await? {
-///         let val = String::from_utf8(chunk.to_vec())?;
-///         println!("{}", val);
+///     while let Some(completion) = stream.next().await {
+///         for choice in completion?.choices {
+///             print!("{}", choice.text.unwrap_or_default());
+///         }
 ///     }
 ///
 ///     Ok(())
 /// }
+/// ```
 #[deprecated(
     since = "0.7.0",
     note = "Please use chat endpoint. More at https://platform.openai.com/docs/guides/text-generation/completions-api"
@@ -89,10 +92,39 @@ pub async fn create(client: &Client, param: &CompletionParam) -> Result<Completi
 pub async fn create_with_stream(
     client: &Client,
     param: &CompletionParam,
-) -> Result<reqwest::Response> {
+) -> Result<BoxStream<'static, Result<Completion>>> {
     client.create_completion_with_stream(param).await
 }
 
+/// Like [`create`], with the given [`RequestOptions`].
+#[deprecated(
+    since = "0.7.0",
+    note = "Please use chat endpoint. More at https://platform.openai.com/docs/guides/text-generation/completions-api"
+)]
+pub async fn create_with(
+    client: &Client,
+    param: &CompletionParam,
+    options: RequestOptions,
+) -> Result<Completion> {
+    client.with_options(options)?.create_completion(param).await
+}
+
+/// Like [`create_with_stream`], with the given [`RequestOptions`].
+#[deprecated(
+    since = "0.7.0",
+    note = "Please use chat endpoint. More at https://platform.openai.com/docs/guides/text-generation/completions-api"
+)]
+pub async fn create_with_stream_with(
+    client: &Client,
+    param: &CompletionParam,
+    options: RequestOptions,
+) -> Result<BoxStream<'static, Result<Completion>>> {
+    client
+        .with_options(options)?
+        .create_completion_with_stream(param)
+        .await
+}
+
 impl Client {
     async fn create_completion(&self, param: &CompletionParam) -> Result<Completion> {
         self.post::<CompletionParam, Completion>("completions", Some(param))
@@ -102,59 +134,82 @@ impl Client {
     async fn create_completion_with_stream(
         &self,
         param: &CompletionParam,
-    ) -> Result<reqwest::Response> {
-        self.post_stream("completions", Some(param)).await
-    }
+    ) -> Result<BoxStream<'static, Result<Completion>>> {
+        let mut param = param.clone();
+        param.stream = true;
 
-    /*
-    fn create_completion_with_stream(
-        &self,
-        param: &CompletionParam,
-    ) -> Pin<
-        Box<
-            dyn Stream<
-                    Item = std::result::Result<Completion, Box<dyn std::error::Error + Send + '_>>,
-                > + Send,
-        >,
-    > {
-        Box::pin(stream! {
-            let mut resp = match self.post_stream("completions", Some(&param)).await {
-                Ok(r) => r,
-                Err(e) => {
-                    yield Err(Box::new(e) as Box<dyn std::error::Error + Send + '_>);
-                    return;
-                }
-            };
-
-            let mut cv = String::new();
-
-            while let Ok(Some(chunk)) = resp.chunk().await {
-                let a = match String::from_utf8(chunk.to_vec()) {
-                    Ok(s) => s,
-                    Err(e) => {
-                        yield Err(Box::new(e) as Box<dyn std::error::Error + Send + '_>);
-                        continue;
-                    }
-                };
-                let whole_val = a.split("data: ").collect::<Vec<_>>();
-
-                for part in whole_val {
-                    match serde_json::from_str::<Completion>(part) {
-                        Ok(v) => yield Ok(v),
-                        Err(_) => {
-                            cv.push_str(part);
-                            if let Ok(v) = serde_json::from_str::<Completion>(&cv) {
-                                cv.clear();
-                                yield Ok(v);
-                            }
-                        }
-                    }
-                }
-            }
-        })
+        self.post_stream::<CompletionParam, Completion>("completions", Some(&param))
+            .await
     }
-    */
 }
 
 #[cfg(test)]
-mod tests {}
+#[allow(deprecated)]
+mod tests {
+    use futures::TryStreamExt;
+
+    use super::*;
+    use crate::{testing::MockTransport, types::CompletionParamBuilder};
+
+    #[tokio::test]
+    async fn test_create() {
+        let mock = MockTransport::with_fixtures();
+        let param = CompletionParamBuilder::new("gpt-3.5-turbo-instruct")
+            .prompt("Say this is a test")
+            .build()
+            .unwrap();
+
+        let resp = create(&mock.client(), &param).await.unwrap();
+
+        assert_eq!(resp.model, "gpt-3.5-turbo-instruct");
+        assert_eq!(
+            resp.choices[0].text.as_deref(),
+            Some("\n\nThis is indeed a test")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_create_with_stream() {
+        let mock = MockTransport::with_fixtures();
+        let param = CompletionParamBuilder::new("gpt-3.5-turbo-instruct")
+            .build()
+            .unwrap();
+
+        let chunks: Vec<Completion> = create_with_stream(&mock.client(), &param)
+            .await
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+        let text: String = chunks
+            .iter()
+            .filter_map(|c| c.choices[0].text.as_deref())
+            .collect();
+
+        assert_eq!(text, "\n\nThis is indeed a test");
+    }
+
+    #[tokio::test]
+    async fn test_create_with_options() {
+        let mock = MockTransport::with_fixtures();
+        let client = mock.client();
+        let param = CompletionParamBuilder::new("gpt-3.5-turbo-instruct")
+            .build()
+            .unwrap();
+        let options = RequestOptions::new().query("api-version", "beta");
+
+        create_with(&client, &param, options.clone()).await.unwrap();
+        let chunks: Vec<Completion> = create_with_stream_with(&client, &param, options)
+            .await
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+
+        assert!(!chunks.is_empty());
+        assert!(mock
+            .requests()
+            .iter()
+            .all(|r| r.query.as_deref() == Some("api-version=beta")));
+    }
+}
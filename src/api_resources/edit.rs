@@ -6,7 +6,7 @@
 
 use crate::{
     types::{Edit, EditParam},
-    Client, Result,
+    Client, RequestOptions, Result,
 };
 
 /// Creates a new edit for the provided input, instruction, and parameters.
@@ -15,7 +15,7 @@ use crate::{
 ///
 /// ## Example
 /// ```no_run
-/// use fieri::{Client, edit::{create, EditParamBuilder}};
+/// use fieri::{Client, edit::create, types::EditParamBuilder};
 ///
 /// #[tokio::main]
 /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -36,6 +36,15 @@ pub async fn create(client: &Client, param: &EditParam) -> Result<Edit> {
     client.create_edit(param).await
 }
 
+/// Like [`create`], with the given [`RequestOptions`].
+pub async fn create_with(
+    client: &Client,
+    param: &EditParam,
+    options: RequestOptions,
+) -> Result<Edit> {
+    create(&client.with_options(options)?, param).await
+}
+
 impl Client {
     async fn create_edit(&self, param: &EditParam) -> Result<Edit> {
         self.post::<EditParam, Edit>("edits", Some(param)).await
@@ -43,4 +52,24 @@ impl Client {
 }
 
 #[cfg(test)]
-mod tests {}
+#[allow(deprecated)]
+mod tests {
+    use super::*;
+    use crate::{testing::MockTransport, types::EditParamBuilder};
+
+    #[tokio::test]
+    async fn test_create() {
+        let mock = MockTransport::with_fixtures();
+        let param = EditParamBuilder::new("text-davinci-edit-001", "Fix the spelling mistakes")
+            .input("What day of the wek is it?")
+            .build()
+            .unwrap();
+
+        let resp = create(&mock.client(), &param).await.unwrap();
+
+        assert_eq!(
+            resp.choices[0].text.as_deref(),
+            Some("What day of the week is it?")
+        );
+    }
+}
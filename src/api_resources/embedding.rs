@@ -10,7 +10,7 @@
 
 use crate::{
     types::{Embedding, EmbeddingParam},
-    Client, Result,
+    Client, RequestOptions, Result,
 };
 
 /// Creates an embedding vector representing the input text.
@@ -19,7 +19,7 @@ use crate::{
 ///
 /// ## Example
 /// ```no_run
-/// use fieri::{Client, embedding::{create, EmbeddingParamBuilder}};
+/// use fieri::{Client, embedding::create, types::EmbeddingParamBuilder};
 ///
 /// #[tokio::main]
 /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -39,6 +39,15 @@ pub async fn create(client: &Client, param: &EmbeddingParam) -> Result<Embedding
     client.create_embeddings(param).await
 }
 
+/// Like [`create`], with the given [`RequestOptions`].
+pub async fn create_with(
+    client: &Client,
+    param: &EmbeddingParam,
+    options: RequestOptions,
+) -> Result<Embedding> {
+    create(&client.with_options(options)?, param).await
+}
+
 impl Client {
     async fn create_embeddings(&self, param: &EmbeddingParam) -> Result<Embedding> {
         self.post::<EmbeddingParam, Embedding>("embeddings", Some(param))
@@ -47,4 +56,20 @@ impl Client {
 }
 
 #[cfg(test)]
-mod tests {}
+mod tests {
+    use super::*;
+    use crate::{testing::MockTransport, types::EmbeddingParamBuilder};
+
+    #[tokio::test]
+    async fn test_create() {
+        let mock = MockTransport::with_fixtures();
+        let param = EmbeddingParamBuilder::new("text-embedding-ada-002", "The food was delicious.")
+            .build()
+            .unwrap();
+
+        let resp = create(&mock.client(), &param).await.unwrap();
+
+        assert_eq!(resp.data[0].embedding.len(), 3);
+        assert_eq!(resp.usage.unwrap().prompt_tokens, 8);
+    }
+}
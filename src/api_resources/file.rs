@@ -1,12 +1,11 @@
 //! Files are used to upload documents that can be used with features like [`Fine-tuning`](crate::api_resources::fine_tune).
 
-use reqwest::multipart::{Form, Part};
-use serde::{Deserialize, Serialize};
 use std::{borrow::Cow, fs, path::Path};
 
 use crate::{
+    multipart::{Form, Part},
     types::{Delete, File, ListFiles, Purpose},
-    Client, Result,
+    Client, RequestOptions, Result,
 };
 
 /// Returns a [`list`][ListFiles] of files that belong to the user's organization.
@@ -39,7 +38,7 @@ pub async fn list(client: &Client) -> Result<ListFiles> {
 /// ## Example
 /// ```no_run
 /// use std::path::Path;
-/// use fieri::{Client, file::{Purpose, upload}};
+/// use fieri::{Client, file::upload, types::Purpose};
 ///
 /// #[tokio::main]
 /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -88,7 +87,7 @@ pub async fn delete(client: &Client, file_id: impl Into<String>) -> Result<Delet
 ///
 /// ## Example
 /// ```no_run
-/// use fieri::{Client, file::retrieve, api_resources::File};
+/// use fieri::{Client, file::retrieve, types::File};
 ///
 /// #[tokio::main]
 /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -105,6 +104,42 @@ pub async fn retrieve(client: &Client, file_id: impl Into<String>) -> Result<Fil
     client.retrieve_file(file_id.into()).await
 }
 
+/// Like [`list`], with the given [`RequestOptions`].
+pub async fn list_with(client: &Client, options: RequestOptions) -> Result<ListFiles> {
+    list(&client.with_options(options)?).await
+}
+
+/// Like [`upload`], with the given [`RequestOptions`].
+pub async fn upload_with<P>(
+    client: &Client,
+    file: P,
+    purpose: Purpose,
+    options: RequestOptions,
+) -> Result<File>
+where
+    P: AsRef<Path> + Into<Cow<'static, str>> + Copy,
+{
+    upload(&client.with_options(options)?, file, purpose).await
+}
+
+/// Like [`delete`], with the given [`RequestOptions`].
+pub async fn delete_with(
+    client: &Client,
+    file_id: impl Into<String>,
+    options: RequestOptions,
+) -> Result<Delete> {
+    delete(&client.with_options(options)?, file_id).await
+}
+
+/// Like [`retrieve`], with the given [`RequestOptions`].
+pub async fn retrieve_with(
+    client: &Client,
+    file_id: impl Into<String>,
+    options: RequestOptions,
+) -> Result<File> {
+    retrieve(&client.with_options(options)?, file_id).await
+}
+
 impl Client {
     async fn list_files(&self) -> Result<ListFiles> {
         self.get::<(), ListFiles>("files", None).await
@@ -115,9 +150,8 @@ impl Client {
         P: AsRef<Path> + Into<Cow<'static, str>> + Copy,
     {
         let data = fs::read(file.as_ref())?;
-        let part = Part::bytes(data).file_name(file);
         let form = Form::new()
-            .part("file", part)
+            .part("file", Part::bytes(data).file_name(file))
             .text("purpose", purpose.to_string());
 
         self.post_data::<File>("files", form).await
@@ -135,4 +169,39 @@ impl Client {
 }
 
 #[cfg(test)]
-mod tests {}
+mod tests {
+    use super::*;
+    use crate::testing::{Method, MockTransport};
+
+    #[tokio::test]
+    async fn test_files() {
+        let mock = MockTransport::with_fixtures();
+        let client = mock.client();
+
+        assert_eq!(list(&client).await.unwrap().data.len(), 2);
+        assert_eq!(retrieve(&client, "file-xyz").await.unwrap().id, "file-xyz");
+        assert!(delete(&client, "file-xyz").await.unwrap().deleted);
+
+        mock.assert_requested(Method::DELETE, "files/file-xyz");
+    }
+
+    #[tokio::test]
+    async fn test_upload() {
+        let mock = MockTransport::with_fixtures();
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/README.md");
+
+        let resp = upload(&mock.client(), path, Purpose::FineTune)
+            .await
+            .unwrap();
+
+        assert_eq!(resp.purpose, "fine-tune");
+
+        let request = mock.assert_requested(Method::POST, "files");
+        let content_type = request.headers["content-type"].to_str().unwrap();
+        assert!(content_type.starts_with("multipart/form-data"));
+
+        let body = String::from_utf8(request.body.unwrap()).unwrap();
+        assert!(body.contains("filename=\""));
+        assert!(body.contains("name=\"purpose\"\r\n\r\nfine-tune\r\n"));
+    }
+}
@@ -15,14 +15,12 @@
 //! Once a model has been fine-tuned, you won't need to provide examples in the prompt anymore.
 //! This saves costs and enables lower-latency requests.
 
-use derive_builder::Builder;
-use serde::{Deserialize, Serialize};
+use futures::stream::BoxStream;
 use serde_json::json;
-use serde_with::skip_serializing_none;
 
 use crate::{
-    types::{CreateFineTuneParam, Delete, FineTune, ListEvents, ListFineTune},
-    Client, Result,
+    types::{CreateFineTuneParam, Delete, Event, FineTune, ListEvents, ListFineTune},
+    Client, RequestOptions, Result,
 };
 
 /// Creates a job that fine-tunes a specified model from a given dataset.
@@ -31,7 +29,7 @@ use crate::{
 ///
 /// ## Example
 /// ```no_run
-/// use fieri::{Client, fine_tune::{create, CreateFineTuneParamBuilder}};
+/// use fieri::{Client, fine_tune::create, types::CreateFineTuneParamBuilder};
 ///
 /// #[tokio::main]
 /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -153,24 +151,26 @@ pub async fn list_events(client: &Client, fine_tune_id: impl Into<String>) -> Re
 ///
 /// ## Example
 /// ```no_run
+/// use futures::StreamExt;
 /// use fieri::{Client, fine_tune::list_events_with_stream};
 ///
 /// #[tokio::main]
 /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
 ///     let client = Client::new();
 ///
-///     let mut resp = list_events_with_stream(&client, "ft-123").await?;
+///     let mut stream = list_events_with_stream(&client, "ft-123").await?;
 ///
-///     while let Some(chunk) = resp.chunk().await? {
-///         println!("{:#?}", chunk);
+///     while let Some(event) = stream.next().await {
+///         println!("{:#?}", event?);
 ///     }
 ///
 ///     Ok(())
 /// }
+/// ```
 pub async fn list_events_with_stream(
     client: &Client,
     fine_tune_id: impl Into<String>,
-) -> Result<reqwest::Response> {
+) -> Result<BoxStream<'static, Result<Event>>> {
     client
         .list_fine_tune_events_with_stream(fine_tune_id.into())
         .await
@@ -198,6 +198,65 @@ pub async fn delete<T: Into<String>>(client: &Client, model: T) -> Result<Delete
     client.delete_fine_tune(model.into()).await
 }
 
+/// Like [`create`], with the given [`RequestOptions`].
+pub async fn create_with(
+    client: &Client,
+    param: &CreateFineTuneParam,
+    options: RequestOptions,
+) -> Result<FineTune> {
+    create(&client.with_options(options)?, param).await
+}
+
+/// Like [`list`], with the given [`RequestOptions`].
+pub async fn list_with(client: &Client, options: RequestOptions) -> Result<ListFineTune> {
+    list(&client.with_options(options)?).await
+}
+
+/// Like [`retrieve`], with the given [`RequestOptions`].
+pub async fn retrieve_with(
+    client: &Client,
+    fine_tune_id: impl Into<String>,
+    options: RequestOptions,
+) -> Result<FineTune> {
+    retrieve(&client.with_options(options)?, fine_tune_id).await
+}
+
+/// Like [`cancel`], with the given [`RequestOptions`].
+pub async fn cancel_with(
+    client: &Client,
+    fine_tune_id: impl Into<String>,
+    options: RequestOptions,
+) -> Result<FineTune> {
+    cancel(&client.with_options(options)?, fine_tune_id).await
+}
+
+/// Like [`list_events`], with the given [`RequestOptions`].
+pub async fn list_events_with(
+    client: &Client,
+    fine_tune_id: impl Into<String>,
+    options: RequestOptions,
+) -> Result<ListEvents> {
+    list_events(&client.with_options(options)?, fine_tune_id).await
+}
+
+/// Like [`list_events_with_stream`], with the given [`RequestOptions`].
+pub async fn list_events_with_stream_with(
+    client: &Client,
+    fine_tune_id: impl Into<String>,
+    options: RequestOptions,
+) -> Result<BoxStream<'static, Result<Event>>> {
+    list_events_with_stream(&client.with_options(options)?, fine_tune_id).await
+}
+
+/// Like [`delete`], with the given [`RequestOptions`].
+pub async fn delete_with<T: Into<String>>(
+    client: &Client,
+    model: T,
+    options: RequestOptions,
+) -> Result<Delete> {
+    delete(&client.with_options(options)?, model).await
+}
+
 impl Client {
     async fn create_fine_tune(&self, param: &CreateFineTuneParam) -> Result<FineTune> {
         self.post::<CreateFineTuneParam, FineTune>("fine-tunes", Some(param))
@@ -226,8 +285,8 @@ impl Client {
     async fn list_fine_tune_events_with_stream(
         &self,
         fine_tune_id: String,
-    ) -> Result<reqwest::Response> {
-        self.get_stream::<serde_json::Value>(
+    ) -> Result<BoxStream<'static, Result<Event>>> {
+        self.get_stream::<serde_json::Value, Event>(
             &format!("fine-tunes/{fine_tune_id}/events"),
             Some(&json!({"stream": true})),
         )
@@ -241,4 +300,56 @@ impl Client {
 }
 
 #[cfg(test)]
-mod tests {}
+mod tests {
+    use futures::TryStreamExt;
+
+    use super::*;
+    use crate::{testing::MockTransport, types::CreateFineTuneParamBuilder};
+
+    #[tokio::test]
+    async fn test_fine_tunes() {
+        let mock = MockTransport::with_fixtures();
+        let client = mock.client();
+
+        let param = CreateFineTuneParamBuilder::new("file-abc123")
+            .build()
+            .unwrap();
+        assert_eq!(create(&client, &param).await.unwrap().status, "pending");
+        assert_eq!(list(&client).await.unwrap().data.len(), 1);
+        assert_eq!(retrieve(&client, "ft-xyz").await.unwrap().id, "ft-xyz");
+        assert_eq!(cancel(&client, "ft-xyz").await.unwrap().status, "cancelled");
+        assert!(delete(&client, "curie:ft-xyz").await.unwrap().deleted);
+    }
+
+    #[tokio::test]
+    async fn test_list_events() {
+        let mock = MockTransport::with_fixtures();
+        let client = mock.client();
+
+        let events = list_events(&client, "ft-abc123").await.unwrap();
+        let streamed: Vec<Event> = list_events_with_stream(&client, "ft-abc123")
+            .await
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+
+        assert_eq!(events.data.len(), 2);
+        assert_eq!(streamed.len(), 2);
+        assert_eq!(streamed[1].message, "Fine-tune succeeded");
+
+        let options = RequestOptions::new().header("OpenAI-Beta", "fine-tunes=v1");
+        let streamed: Vec<Event> = list_events_with_stream_with(&client, "ft-abc123", options)
+            .await
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+
+        assert_eq!(streamed.len(), 2);
+        assert_eq!(
+            mock.requests().last().unwrap().headers["openai-beta"],
+            "fine-tunes=v1"
+        );
+    }
+}
@@ -5,24 +5,12 @@
 //! - Creating edits of an existing image based on a new text prompt
 //! - Creating variations of an existing image
 
-use derive_builder::Builder;
-use reqwest::{
-    get,
-    multipart::{Form, Part},
-};
-use serde::{Deserialize, Serialize};
-use serde_with::skip_serializing_none;
-use std::{
-    borrow::Cow,
-    fs,
-    io::{copy, Cursor},
-    path::Path,
-    str::FromStr,
-};
+use std::{borrow::Cow, fs, path::Path};
 
 use crate::{
+    multipart::{Form, Part},
     types::{EditImageParam, GenerateImageParam, Image, VariateImageParam},
-    Client, Result,
+    Client, RequestOptions, Result,
 };
 
 /// The image generations endpoint allows you to create an original image given a text prompt. Generated images can have a size of `256x256`, `512x512`, or `1024x1024` pixels.
@@ -33,7 +21,7 @@ use crate::{
 ///
 /// ## Example
 /// ```no_run
-/// use fieri::{Client, image::{ImageSize, GenerateImageParamBuilder, generate}};
+/// use fieri::{Client, image::generate, types::{ImageSize, GenerateImageParamBuilder}};
 ///
 /// #[tokio::main]
 /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -58,7 +46,7 @@ pub async fn generate(client: &Client, param: &GenerateImageParam) -> Result<Ima
 ///
 /// ## Example
 /// ```no_run
-/// use fieri::{Client, image::{ImageSize, EditImageParamBuilder, edit}};
+/// use fieri::{Client, image::edit, types::{ImageSize, EditImageParamBuilder}};
 ///
 /// #[tokio::main]
 /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -86,7 +74,7 @@ where
 ///
 /// ## Example
 /// ```no_run
-/// use fieri::{Client, image::{ImageSize, VariateImageParamBuilder, variate}};
+/// use fieri::{Client, image::variate, types::{ImageSize, VariateImageParamBuilder}};
 ///
 /// #[tokio::main]
 /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -109,6 +97,41 @@ where
     client.variate_image(image, param).await
 }
 
+/// Like [`generate`], with the given [`RequestOptions`].
+pub async fn generate_with(
+    client: &Client,
+    param: &GenerateImageParam,
+    options: RequestOptions,
+) -> Result<Image> {
+    generate(&client.with_options(options)?, param).await
+}
+
+/// Like [`edit`], with the given [`RequestOptions`].
+pub async fn edit_with<P>(
+    client: &Client,
+    image: P,
+    param: &EditImageParam,
+    options: RequestOptions,
+) -> Result<Image>
+where
+    P: AsRef<Path> + Into<Cow<'static, str>> + Copy,
+{
+    edit(&client.with_options(options)?, image, param).await
+}
+
+/// Like [`variate`], with the given [`RequestOptions`].
+pub async fn variate_with<P>(
+    client: &Client,
+    image: P,
+    param: &VariateImageParam,
+    options: RequestOptions,
+) -> Result<Image>
+where
+    P: AsRef<Path> + Into<Cow<'static, str>> + Copy,
+{
+    variate(&client.with_options(options)?, image, param).await
+}
+
 impl Client {
     async fn generate_image(&self, param: &GenerateImageParam) -> Result<Image> {
         self.post::<GenerateImageParam, Image>("images/generations", Some(param))
@@ -120,9 +143,8 @@ impl Client {
         P: AsRef<Path> + Into<Cow<'static, str>> + Copy,
     {
         let data = fs::read(image)?;
-        let part = Part::bytes(data).file_name(image);
         let form = Form::new()
-            .part("image", part)
+            .part("image", Part::bytes(data).file_name(image))
             .text("prompt", "22")
             .text("n", param.n.to_string())
             .text("size", param.size.to_string())
@@ -136,9 +158,8 @@ impl Client {
         P: AsRef<Path> + Into<Cow<'static, str>> + Copy,
     {
         let data = fs::read(image)?;
-        let part = Part::bytes(data).file_name(image);
         let form = Form::new()
-            .part("image", part)
+            .part("image", Part::bytes(data).file_name(image))
             .text("n", param.n.to_string())
             .text("size", param.size.to_string())
             .text("user", param.user.to_string());
@@ -150,4 +171,34 @@ impl Client {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{
+        testing::{Method, MockTransport},
+        types::{GenerateImageParamBuilder, VariateImageParamBuilder},
+    };
+
+    #[tokio::test]
+    async fn test_generate() {
+        let mock = MockTransport::with_fixtures();
+        let param = GenerateImageParamBuilder::new(
+            "A bunch of cats dancing tango on top of the highest mountain on Mars.",
+        )
+        .build()
+        .unwrap();
+
+        let resp = generate(&mock.client(), &param).await.unwrap();
+
+        assert_eq!(resp.data.unwrap()[0].url, "https://example.com/image.png");
+    }
+
+    #[tokio::test]
+    async fn test_variate() {
+        let mock = MockTransport::with_fixtures();
+        let param = VariateImageParamBuilder::new().build().unwrap();
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/README.md");
+
+        let resp = variate(&mock.client(), path, &param).await.unwrap();
+
+        assert!(resp.data.is_some());
+        mock.assert_requested(Method::POST, "images/variations");
+    }
 }
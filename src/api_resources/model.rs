@@ -1,10 +1,8 @@
 //! List and describe the various models available in the API.
 
-use serde::{Deserialize, Serialize};
-
 use crate::{
     types::{Model, Models},
-    Client, Result,
+    Client, RequestOptions, Result,
 };
 
 /// Retrieves a model instance, providing basic information about the model such as the owner and permissioning.
@@ -49,6 +47,20 @@ pub async fn list(client: &Client) -> Result<Models> {
     client.list().await
 }
 
+/// Like [`retrieve`], with the given [`RequestOptions`].
+pub async fn retrieve_with(
+    client: &Client,
+    model: impl Into<String>,
+    options: RequestOptions,
+) -> Result<Model> {
+    retrieve(&client.with_options(options)?, model).await
+}
+
+/// Like [`list`], with the given [`RequestOptions`].
+pub async fn list_with(client: &Client, options: RequestOptions) -> Result<Models> {
+    list(&client.with_options(options)?).await
+}
+
 impl Client {
     async fn retrieve(&self, model: String) -> Result<Model> {
         self.get::<(), Model>(&format!("models/{model}"), None)
@@ -63,5 +75,37 @@ impl Client {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{
+        testing::{Method, MockResponse, MockTransport},
+        Error,
+    };
+
+    #[tokio::test]
+    async fn test_models() {
+        let mock = MockTransport::with_fixtures();
+        let client = mock.client();
 
+        assert_eq!(list(&client).await.unwrap().data.len(), 2);
+        assert_eq!(retrieve(&client, "gpt-4").await.unwrap().id, "gpt-4");
+    }
+
+    #[tokio::test]
+    async fn test_retrieve_missing() {
+        let mock = MockTransport::with_fixtures();
+        mock.on(
+            Method::GET,
+            "models/*",
+            MockResponse::error(
+                404,
+                "The model `gpt-5` does not exist",
+                "invalid_request_error",
+                Some("model_not_found"),
+            ),
+        );
+
+        let err = retrieve(&mock.client(), "gpt-5").await.unwrap_err();
+
+        assert!(matches!(err, Error::NotFound(_)));
+        assert_eq!(err.request_id(), Some("req_mock"));
+    }
 }
@@ -9,13 +9,9 @@
 //! - Violence - Content that promotes or glorifies violence or celebrates the suffering or humiliation of others.
 //! - Violence/graphic - Violent content that depicts death, violence, or serious physical injury in extreme graphic detail.
 
-use derive_builder::Builder;
-use serde::{Deserialize, Serialize};
-use serde_with::skip_serializing_none;
-
 use crate::{
     types::{Moderation, ModerationParam},
-    Client, Result,
+    Client, RequestOptions, Result,
 };
 
 /// Classifies if text violates OpenAI's Content Policy.
@@ -24,7 +20,7 @@ use crate::{
 ///
 /// ## Example
 /// ```no_run
-/// use fieri::{Client, moderation::{ModerationParamBuilder, create}};
+/// use fieri::{Client, moderation::create, types::ModerationParamBuilder};
 ///
 /// #[tokio::main]
 /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -44,6 +40,15 @@ pub async fn create(client: &Client, param: &ModerationParam) -> Result<Moderati
     client.create_moderation(param).await
 }
 
+/// Like [`create`], with the given [`RequestOptions`].
+pub async fn create_with(
+    client: &Client,
+    param: &ModerationParam,
+    options: RequestOptions,
+) -> Result<Moderation> {
+    create(&client.with_options(options)?, param).await
+}
+
 impl Client {
     async fn create_moderation(&self, param: &ModerationParam) -> Result<Moderation> {
         self.post::<ModerationParam, Moderation>("moderations", Some(param))
@@ -54,4 +59,18 @@ impl Client {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{testing::MockTransport, types::ModerationParamBuilder};
+
+    #[tokio::test]
+    async fn test_create() {
+        let mock = MockTransport::with_fixtures();
+        let param = ModerationParamBuilder::new("I want to kill them.")
+            .build()
+            .unwrap();
+
+        let resp = create(&mock.client(), &param).await.unwrap();
+
+        assert!(resp.results[0].categories.violence);
+        assert!(resp.results[0].category_scores.violence > 0.9);
+    }
 }
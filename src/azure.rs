@@ -0,0 +1,225 @@
+//! Support for [Azure OpenAI](https://learn.microsoft.com/azure/ai-services/openai/reference).
+//!
+//! Azure serves each model from a named deployment, so requests like [`chat`](crate::chat::chat)
+//! are sent to `{endpoint}/openai/deployments/{deployment}/chat/completions?api-version=...`,
+//! and are authorized with an `api-key` header instead of a bearer token.
+//!
+//! The deployment is looked up by the `model` of each request, falling back to the model name itself.
+//!
+//! ## Usage
+//! ```no_run
+//! use fieri::{azure::AzureConfig, Client};
+//!
+//! # fn main() -> Result<(), fieri::Error> {
+//! let client = Client::builder()
+//!     .api_key("<azure-api-key>")
+//!     .azure(
+//!         AzureConfig::new("https://my-resource.openai.azure.com", "2024-02-01")
+//!             .deployment("gpt-4", "my-gpt4-deployment"),
+//!     )
+//!     .build()?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::{Error, Result};
+
+/// Endpoints that are served by a specific deployment, rather than the resource as a whole.
+const DEPLOYMENT_ENDPOINTS: &[&str] = &[
+    "chat/completions",
+    "completions",
+    "embeddings",
+    "images/generations",
+    "images/edits",
+    "images/variations",
+];
+
+/// The configuration needed to talk to an Azure OpenAI resource.
+#[derive(Clone, Debug, Default)]
+pub struct AzureConfig {
+    /// The endpoint of the resource, like `https://my-resource.openai.azure.com`.
+    pub endpoint: String,
+
+    /// The version of the API, sent as the `api-version` query parameter of each request.
+    pub api_version: String,
+
+    /// The deployment serving each model.
+    pub deployments: HashMap<String, String>,
+
+    /// The deployment used for requests without a model, like image edits.
+    pub default_deployment: Option<String>,
+}
+
+impl AzureConfig {
+    pub fn new(endpoint: impl Into<String>, api_version: impl Into<String>) -> Self {
+        Self {
+            endpoint: endpoint.into(),
+            api_version: api_version.into(),
+            ..Self::default()
+        }
+    }
+
+    /// Send requests for the given model to the given deployment.
+    pub fn deployment(mut self, model: impl Into<String>, deployment: impl Into<String>) -> Self {
+        self.deployments.insert(model.into(), deployment.into());
+
+        self
+    }
+
+    pub fn default_deployment(mut self, deployment: impl Into<String>) -> Self {
+        self.default_deployment = Some(deployment.into());
+
+        self
+    }
+
+    /// Rewrites the path of an OpenAI endpoint to its Azure counterpart.
+    pub(crate) fn url(&self, base: &Url, identifier: &str, model: Option<&str>) -> Result<Url> {
+        let mut url = if DEPLOYMENT_ENDPOINTS.contains(&identifier) {
+            let deployment = model
+                .map(|m| self.deployments.get(m).map(String::as_str).unwrap_or(m))
+                .or(self.default_deployment.as_deref())
+                .ok_or_else(|| Error::MissingDeployment(identifier.to_string()))?;
+
+            // Pushed as a single segment, so that it's percent-encoded rather than read as a path or a query.
+            let mut url = base.join("openai/deployments/")?;
+            url.path_segments_mut()
+                .map_err(|_| url::ParseError::RelativeUrlWithCannotBeABaseBase)?
+                .pop_if_empty()
+                .push(deployment)
+                .extend(identifier.split('/'));
+
+            url
+        } else {
+            base.join(&format!("openai/{identifier}"))?
+        };
+
+        url.query_pairs_mut()
+            .append_pair("api-version", &self.api_version);
+
+        Ok(url)
+    }
+}
+
+/// The results of Azure's content filtering, per category.
+///
+/// Returned with [`errors`](crate::error::InnerError) when a prompt or a completion has been filtered.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ContentFilterResults {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hate: Option<ContentFilterResult>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub self_harm: Option<ContentFilterResult>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sexual: Option<ContentFilterResult>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub violence: Option<ContentFilterResult>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub jailbreak: Option<ContentFilterResult>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub profanity: Option<ContentFilterResult>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ContentFilterResult {
+    pub filtered: bool,
+
+    /// One of `safe`, `low`, `medium` or `high`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub severity: Option<String>,
+
+    /// Set for categories that are detected rather than graded, like `jailbreak`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detected: Option<bool>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::RequestError;
+
+    #[test]
+    fn test_url() {
+        let base = Url::parse("https://my-resource.openai.azure.com/").unwrap();
+        let azure = AzureConfig::new(base.as_str(), "2024-02-01").deployment("gpt-4", "prod-gpt4");
+
+        assert_eq!(
+            azure
+                .url(&base, "chat/completions", Some("gpt-4"))
+                .unwrap()
+                .as_str(),
+            "https://my-resource.openai.azure.com/openai/deployments/prod-gpt4/chat/completions?api-version=2024-02-01"
+        );
+        assert_eq!(
+            azure
+                .url(&base, "embeddings", Some("ada-embeddings"))
+                .unwrap()
+                .as_str(),
+            "https://my-resource.openai.azure.com/openai/deployments/ada-embeddings/embeddings?api-version=2024-02-01"
+        );
+        assert_eq!(
+            azure.url(&base, "files/file-123", None).unwrap().as_str(),
+            "https://my-resource.openai.azure.com/openai/files/file-123?api-version=2024-02-01"
+        );
+        assert!(matches!(
+            azure.url(&base, "images/edits", None),
+            Err(Error::MissingDeployment(_))
+        ));
+
+        let azure = azure.deployment("gpt-4o", "team a/gpt-4o?x=1#");
+        assert_eq!(
+            azure
+                .url(&base, "chat/completions", Some("gpt-4o"))
+                .unwrap()
+                .as_str(),
+            "https://my-resource.openai.azure.com/openai/deployments/team%20a%2Fgpt-4o%3Fx=1%23/chat/completions?api-version=2024-02-01"
+        );
+    }
+
+    #[test]
+    fn test_content_filter_error_deserialization() {
+        let err: RequestError = serde_json::from_str(
+            r#"
+            {
+                "error": {
+                    "message": "The response was filtered due to the prompt triggering Azure OpenAI's content management policy.",
+                    "type": null,
+                    "param": "prompt",
+                    "code": "content_filter",
+                    "status": 400,
+                    "innererror": {
+                        "code": "ResponsibleAIPolicyViolation",
+                        "content_filter_result": {
+                            "hate": {"filtered": false, "severity": "safe"},
+                            "jailbreak": {"filtered": false, "detected": false},
+                            "self_harm": {"filtered": false, "severity": "safe"},
+                            "sexual": {"filtered": false, "severity": "safe"},
+                            "violence": {"filtered": true, "severity": "medium"}
+                        }
+                    }
+                }
+            }
+            "#,
+        )
+        .unwrap();
+
+        let inner = err.error.innererror.unwrap();
+        let results = inner.content_filter_result.unwrap();
+
+        assert_eq!(err.error.r#type, "");
+        assert_eq!(inner.code, Some("ResponsibleAIPolicyViolation".to_string()));
+        assert!(results.violence.unwrap().filtered);
+        assert_eq!(results.jailbreak.unwrap().detected, Some(false));
+    }
+}
@@ -2,15 +2,17 @@ use std::env;
 use std::path::PathBuf;
 
 use clap::Parser;
+use futures::StreamExt;
 
 use fieri::{
-    chat::chat,
+    chat::{chat, chat_stream},
     types::{ChatParam, ChatRole},
     Client,
 };
 use rustyline::{error::ReadlineError, DefaultEditor};
 
 mod version;
+use version::{LONG_VERSION, SHORT_VERSION};
 
 fn history_path() -> PathBuf {
     let mut path = PathBuf::from(env::var("HOME").unwrap());
@@ -20,6 +22,7 @@ fn history_path() -> PathBuf {
 }
 
 #[derive(Clone, Parser, Debug)]
+#[allow(clippy::large_enum_variant)]
 enum Commands {
     /// Opens a REPL console
     Console,
@@ -31,13 +34,14 @@ enum Commands {
         #[clap(short, long, default_value = "user")]
         role: ChatRole,
 
-        #[clap(short, long, default_value = "")]
-        name: String,
+        /// The name of the author of the messages.
+        #[clap(short, long)]
+        name: Option<String>,
     },
 }
 
 #[derive(Parser, Debug, Clone)]
-#[clap(author, version, about="OpenAI command-line interface.", long_about = None)]
+#[clap(author, version = SHORT_VERSION, long_version = LONG_VERSION, about="OpenAI command-line interface.", long_about = None)]
 struct Cli {
     #[clap(subcommand)]
     command: Commands,
@@ -95,15 +99,27 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         Commands::Chat {
             mut param,
             role,
-            name: _,
+            name,
         } => {
             param.messages.iter_mut().for_each(|m| {
                 m.role = role;
+                m.name = name.clone();
             });
             let param = ChatParam { ..param };
             println!("{:#?}", param);
-            let resp = chat(&client, &param).await?;
-            println!("{:#?}", resp);
+
+            if param.stream {
+                let mut stream = chat_stream(&client, &param).await?;
+                while let Some(chunk) = stream.next().await {
+                    for choice in chunk?.choices {
+                        print!("{}", choice.delta.content.unwrap_or_default());
+                    }
+                }
+                println!();
+            } else {
+                let resp = chat(&client, &param).await?;
+                println!("{:#?}", resp);
+            }
             //println!("{:#?}", resp.choices[0].message.content);
         }
     }
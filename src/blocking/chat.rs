@@ -0,0 +1,19 @@
+//! Blocking counterparts of [`fieri::chat`](crate::chat).
+
+use super::{Client, Stream};
+use crate::{
+    types::{Chat, ChatChunk, ChatParam},
+    Result,
+};
+
+/// See [`fieri::chat::chat`](crate::chat::chat).
+pub fn chat(client: &Client, param: &ChatParam) -> Result<Chat> {
+    client.block_on(crate::chat::chat(&client.inner, param))
+}
+
+/// See [`fieri::chat::chat_stream`](crate::chat::chat_stream).
+pub fn chat_stream(client: &Client, param: &ChatParam) -> Result<Stream<ChatChunk>> {
+    let stream = client.block_on(crate::chat::chat_stream(&client.inner, param))?;
+
+    Ok(client.stream(stream))
+}
@@ -0,0 +1,12 @@
+//! Blocking counterparts of [`fieri::embedding`](crate::embedding).
+
+use super::Client;
+use crate::{
+    types::{Embedding, EmbeddingParam},
+    Result,
+};
+
+/// See [`fieri::embedding::create`](crate::embedding::create).
+pub fn create(client: &Client, param: &EmbeddingParam) -> Result<Embedding> {
+    client.block_on(crate::embedding::create(&client.inner, param))
+}
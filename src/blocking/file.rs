@@ -0,0 +1,32 @@
+//! Blocking counterparts of [`fieri::file`](crate::file).
+
+use std::{borrow::Cow, path::Path};
+
+use super::Client;
+use crate::{
+    types::{Delete, File, ListFiles, Purpose},
+    Result,
+};
+
+/// See [`fieri::file::list`](crate::file::list).
+pub fn list(client: &Client) -> Result<ListFiles> {
+    client.block_on(crate::file::list(&client.inner))
+}
+
+/// See [`fieri::file::upload`](crate::file::upload).
+pub fn upload<P>(client: &Client, file: P, purpose: Purpose) -> Result<File>
+where
+    P: AsRef<Path> + Into<Cow<'static, str>> + Copy,
+{
+    client.block_on(crate::file::upload(&client.inner, file, purpose))
+}
+
+/// See [`fieri::file::delete`](crate::file::delete).
+pub fn delete(client: &Client, file_id: impl Into<String>) -> Result<Delete> {
+    client.block_on(crate::file::delete(&client.inner, file_id))
+}
+
+/// See [`fieri::file::retrieve`](crate::file::retrieve).
+pub fn retrieve(client: &Client, file_id: impl Into<String>) -> Result<File> {
+    client.block_on(crate::file::retrieve(&client.inner, file_id))
+}
@@ -0,0 +1,50 @@
+//! Blocking counterparts of [`fieri::fine_tune`](crate::fine_tune).
+
+use super::{Client, Stream};
+use crate::{
+    types::{CreateFineTuneParam, Delete, Event, FineTune, ListEvents, ListFineTune},
+    Result,
+};
+
+/// See [`fieri::fine_tune::create`](crate::fine_tune::create).
+pub fn create(client: &Client, param: &CreateFineTuneParam) -> Result<FineTune> {
+    client.block_on(crate::fine_tune::create(&client.inner, param))
+}
+
+/// See [`fieri::fine_tune::list`](crate::fine_tune::list).
+pub fn list(client: &Client) -> Result<ListFineTune> {
+    client.block_on(crate::fine_tune::list(&client.inner))
+}
+
+/// See [`fieri::fine_tune::retrieve`](crate::fine_tune::retrieve).
+pub fn retrieve(client: &Client, fine_tune_id: impl Into<String>) -> Result<FineTune> {
+    client.block_on(crate::fine_tune::retrieve(&client.inner, fine_tune_id))
+}
+
+/// See [`fieri::fine_tune::cancel`](crate::fine_tune::cancel).
+pub fn cancel(client: &Client, fine_tune_id: impl Into<String>) -> Result<FineTune> {
+    client.block_on(crate::fine_tune::cancel(&client.inner, fine_tune_id))
+}
+
+/// See [`fieri::fine_tune::list_events`](crate::fine_tune::list_events).
+pub fn list_events(client: &Client, fine_tune_id: impl Into<String>) -> Result<ListEvents> {
+    client.block_on(crate::fine_tune::list_events(&client.inner, fine_tune_id))
+}
+
+/// See [`fieri::fine_tune::list_events_with_stream`](crate::fine_tune::list_events_with_stream).
+pub fn list_events_with_stream(
+    client: &Client,
+    fine_tune_id: impl Into<String>,
+) -> Result<Stream<Event>> {
+    let stream = client.block_on(crate::fine_tune::list_events_with_stream(
+        &client.inner,
+        fine_tune_id,
+    ))?;
+
+    Ok(client.stream(stream))
+}
+
+/// See [`fieri::fine_tune::delete`](crate::fine_tune::delete).
+pub fn delete<T: Into<String>>(client: &Client, model: T) -> Result<Delete> {
+    client.block_on(crate::fine_tune::delete(&client.inner, model))
+}
@@ -0,0 +1,30 @@
+//! Blocking counterparts of [`fieri::image`](crate::image).
+
+use std::{borrow::Cow, path::Path};
+
+use super::Client;
+use crate::{
+    types::{EditImageParam, GenerateImageParam, Image, VariateImageParam},
+    Result,
+};
+
+/// See [`fieri::image::generate`](crate::image::generate).
+pub fn generate(client: &Client, param: &GenerateImageParam) -> Result<Image> {
+    client.block_on(crate::image::generate(&client.inner, param))
+}
+
+/// See [`fieri::image::edit`](crate::image::edit).
+pub fn edit<P>(client: &Client, image: P, param: &EditImageParam) -> Result<Image>
+where
+    P: AsRef<Path> + Into<Cow<'static, str>> + Copy,
+{
+    client.block_on(crate::image::edit(&client.inner, image, param))
+}
+
+/// See [`fieri::image::variate`](crate::image::variate).
+pub fn variate<P>(client: &Client, image: P, param: &VariateImageParam) -> Result<Image>
+where
+    P: AsRef<Path> + Into<Cow<'static, str>> + Copy,
+{
+    client.block_on(crate::image::variate(&client.inner, image, param))
+}
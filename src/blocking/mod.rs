@@ -0,0 +1,229 @@
+//! A blocking [`Client`], for synchronous callers that don't run an async runtime of their own.
+//!
+//! The endpoints mirror their async counterparts, taking & returning the same [`types`](crate::types),
+//! while streams are returned as blocking iterators.
+//!
+//! Each client owns a small runtime, shared between its clones, executing requests in the background.
+//! Calling it from within an async runtime panics, so async code should use [`fieri::Client`](crate::Client) instead.
+//!
+//! Only available with the `blocking` feature.
+//!
+//! ## Usage
+//! ```no_run
+//! use fieri::{
+//!     blocking::{chat::chat_stream, Client},
+//!     types::{ChatMessageBuilder, ChatParamBuilder},
+//! };
+//!
+//! fn main() -> Result<(), Box<dyn std::error::Error>> {
+//!     let client = Client::new();
+//!
+//!     let message = ChatMessageBuilder::new("user", "Hello!").build()?;
+//!     let param = ChatParamBuilder::new("gpt-3.5-turbo", vec![message]).build()?;
+//!
+//!     for chunk in chat_stream(&client, &param)? {
+//!         for choice in chunk?.choices {
+//!             print!("{}", choice.delta.content.unwrap_or_default());
+//!         }
+//!     }
+//!
+//!     Ok(())
+//! }
+//! ```
+
+pub mod chat;
+pub mod embedding;
+pub mod file;
+pub mod fine_tune;
+pub mod image;
+pub mod model;
+pub mod moderation;
+
+use std::{fmt, future::Future, sync::Arc};
+
+use futures::{stream::BoxStream, StreamExt};
+use tokio::runtime::Runtime;
+
+use crate::{ClientBuilder, Result};
+
+/// The blocking counterpart of [`fieri::Client`](crate::Client).
+#[derive(Clone)]
+pub struct Client {
+    inner: crate::Client,
+    runtime: Arc<Runtime>,
+}
+
+impl Client {
+    /// Creates a new instance of the Client, configured like [`fieri::Client::new`](crate::Client::new).
+    ///
+    /// Use [`ClientBuilder::build_blocking`] to handle errors instead of panicking, or to customize the client.
+    pub fn new() -> Self {
+        ClientBuilder::new()
+            .build_blocking()
+            .expect("Err creating blocking client.")
+    }
+
+    /// Wraps the given async client, keeping its configuration.
+    pub fn from_async(client: crate::Client) -> Result<Self> {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(1)
+            .thread_name("fieri-blocking")
+            .enable_all()
+            .build()?;
+
+        Ok(Self {
+            inner: client,
+            runtime: Arc::new(runtime),
+        })
+    }
+
+    /// See [`fieri::Client::api_key`](crate::Client::api_key).
+    pub fn api_key<T: Into<String>>(mut self, api_key: T) -> Self {
+        self.inner = self.inner.api_key(api_key);
+
+        self
+    }
+
+    /// See [`fieri::Client::organization`](crate::Client::organization).
+    pub fn organization<T: Into<String>>(mut self, organization: T) -> Self {
+        self.inner = self.inner.organization(organization);
+
+        self
+    }
+
+    /// The async client executing the requests.
+    pub fn inner(&self) -> &crate::Client {
+        &self.inner
+    }
+
+    fn block_on<F: Future>(&self, future: F) -> F::Output {
+        self.runtime.block_on(future)
+    }
+
+    fn stream<T>(&self, stream: BoxStream<'static, Result<T>>) -> Stream<T> {
+        Stream {
+            inner: stream,
+            runtime: self.runtime.clone(),
+        }
+    }
+}
+
+impl Default for Client {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for Client {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Client")
+            .field("inner", &self.inner)
+            .finish()
+    }
+}
+
+impl ClientBuilder {
+    /// Builds a blocking [`Client`].
+    pub fn build_blocking(self) -> Result<Client> {
+        Client::from_async(self.build()?)
+    }
+}
+
+/// A streamed response, blocking until each item arrives.
+pub struct Stream<T> {
+    inner: BoxStream<'static, Result<T>>,
+    runtime: Arc<Runtime>,
+}
+
+impl<T> Iterator for Stream<T> {
+    type Item = Result<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.runtime.block_on(self.inner.next())
+    }
+}
+
+impl<T> fmt::Debug for Stream<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Stream")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        testing::{Method, MockResponse, MockTransport},
+        types::{ChatMessageBuilder, ChatParamBuilder, ChatStreamAccumulator, Purpose},
+        Error,
+    };
+
+    fn client(mock: &MockTransport) -> Client {
+        Client::from_async(mock.client()).unwrap()
+    }
+
+    #[test]
+    fn test_chat() {
+        let mock = MockTransport::with_fixtures();
+        let client = client(&mock);
+        let message = ChatMessageBuilder::new("user", "Hello!").build().unwrap();
+        let param = ChatParamBuilder::new("gpt-3.5-turbo", vec![message])
+            .build()
+            .unwrap();
+
+        let resp = chat::chat(&client, &param).unwrap();
+        assert_eq!(
+            resp.choices[0].message.content,
+            "Hello! How can I help you today?"
+        );
+
+        let mut acc = ChatStreamAccumulator::new();
+        for chunk in chat::chat_stream(&client, &param).unwrap() {
+            acc.push(&chunk.unwrap());
+        }
+        assert_eq!(
+            acc.finish().choices[0].message.content,
+            "Hello! How can I help you today?"
+        );
+        assert!(mock.requests()[1].is_stream());
+    }
+
+    #[test]
+    fn test_endpoints() {
+        let mock = MockTransport::with_fixtures();
+        mock.once(
+            Method::GET,
+            "models/*",
+            MockResponse::error(
+                404,
+                "The model does not exist",
+                "invalid_request_error",
+                None,
+            ),
+        );
+        let client = client(&mock);
+
+        assert!(matches!(
+            model::retrieve(&client, "gpt-5"),
+            Err(Error::NotFound(_))
+        ));
+        assert_eq!(model::list(&client).unwrap().data.len(), 2);
+        assert_eq!(file::list(&client).unwrap().data.len(), 2);
+        assert_eq!(
+            file::upload(
+                &client,
+                concat!(env!("CARGO_MANIFEST_DIR"), "/README.md"),
+                Purpose::FineTune
+            )
+            .unwrap()
+            .id,
+            "file-abc123"
+        );
+
+        let events: Vec<_> = fine_tune::list_events_with_stream(&client, "ft-abc123")
+            .unwrap()
+            .collect::<Result<_>>()
+            .unwrap();
+        assert_eq!(events.len(), 2);
+    }
+}
@@ -0,0 +1,17 @@
+//! Blocking counterparts of [`fieri::model`](crate::model).
+
+use super::Client;
+use crate::{
+    types::{Model, Models},
+    Result,
+};
+
+/// See [`fieri::model::retrieve`](crate::model::retrieve).
+pub fn retrieve(client: &Client, model: impl Into<String>) -> Result<Model> {
+    client.block_on(crate::model::retrieve(&client.inner, model))
+}
+
+/// See [`fieri::model::list`](crate::model::list).
+pub fn list(client: &Client) -> Result<Models> {
+    client.block_on(crate::model::list(&client.inner))
+}
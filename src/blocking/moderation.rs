@@ -0,0 +1,12 @@
+//! Blocking counterparts of [`fieri::moderation`](crate::moderation).
+
+use super::Client;
+use crate::{
+    types::{Moderation, ModerationParam},
+    Result,
+};
+
+/// See [`fieri::moderation::create`](crate::moderation::create).
+pub fn create(client: &Client, param: &ModerationParam) -> Result<Moderation> {
+    client.block_on(crate::moderation::create(&client.inner, param))
+}
@@ -0,0 +1,671 @@
+//! Caching the responses of deterministic requests, like embeddings of unchanged documents.
+//!
+//! Once a [`Cache`] is attached to a [`Client`](crate::Client), successful responses of the cached endpoints
+//! are stored under a hash of the URL, the headers & the body of their request, as sent with its options & credentials,
+//! and identical requests are answered from the store.
+//! Identical requests sent concurrently are coalesced: only the first one is sent, while the others wait for its response.
+//!
+//! Endpoints that sample their output, like `chat/completions`, are only cached for deterministic requests:
+//! the ones with a `temperature` of 0, or a `seed`. Other requests would otherwise get the same reply every time,
+//! unless sampled requests are explicitly opted into with [`Cache::cache_sampled`].
+//!
+//! Streams, uploads & failed requests are never cached.
+//! Clones of a [`Cache`], including the ones held by clones of a [`Client`](crate::Client), share the same store.
+//!
+//! ## Usage
+//! ```no_run
+//! use std::time::Duration;
+//! use fieri::{
+//!     cache::{Cache, FileStore, MemoryStore},
+//!     Client,
+//! };
+//!
+//! let client = Client::new().cache(Cache::new(
+//!     MemoryStore::new()
+//!         .max_entries(10_000)
+//!         .ttl(Duration::from_secs(3600)),
+//! ));
+//!
+//! let client = Client::new().cache(Cache::new(
+//!     FileStore::new(".cache/openai").max_bytes(512 * 1024 * 1024),
+//! ));
+//! ```
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt, fs,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant, SystemTime},
+};
+
+use serde::Serialize;
+use serde_json::Value;
+use tokio::sync::OwnedMutexGuard;
+
+use crate::transport::Request;
+
+/// Endpoints cached by default.
+const ENDPOINTS: &[&str] = &["chat/completions", "embeddings", "moderations"];
+
+/// Endpoints sampling their output, only cached for deterministic requests.
+const SAMPLING_ENDPOINTS: &[&str] = &["chat/completions", "completions", "edits"];
+
+/// Where cached responses are kept.
+///
+/// Stores are best-effort: failing to read or write an entry is treated as a miss.
+pub trait CacheStore: Send + Sync + 'static {
+    /// The response stored under the given key, unless it's missing or expired.
+    fn get(&self, key: &str) -> Option<Vec<u8>>;
+
+    fn put(&self, key: &str, value: Vec<u8>);
+}
+
+/// Answers identical requests from a [`CacheStore`].
+#[derive(Clone)]
+pub struct Cache {
+    store: Arc<dyn CacheStore>,
+    endpoints: Vec<String>,
+
+    /// Sampling endpoints cached even for requests that aren't deterministic.
+    sampled: Vec<String>,
+
+    /// A lock for each key with a request in flight.
+    in_flight: Arc<Mutex<HashMap<String, Arc<tokio::sync::Mutex<()>>>>>,
+}
+
+impl Cache {
+    /// Caches the `chat/completions`, `embeddings` & `moderations` endpoints in the given store.
+    pub fn new(store: impl CacheStore) -> Self {
+        Self {
+            store: Arc::new(store),
+            endpoints: ENDPOINTS.iter().map(|e| e.to_string()).collect(),
+            sampled: Vec::new(),
+            in_flight: Arc::default(),
+        }
+    }
+
+    /// Cache the given endpoints, like `completions`, instead of the default ones.
+    pub fn endpoints<T: Into<String>>(mut self, endpoints: impl IntoIterator<Item = T>) -> Self {
+        self.endpoints = endpoints.into_iter().map(Into::into).collect();
+
+        self
+    }
+
+    /// Also cache requests to the given endpoints that aren't deterministic, like chats with a `temperature` above 0 and no `seed`.
+    ///
+    /// Identical requests then get the same reply every time, instead of a new sample.
+    pub fn cache_sampled<T: Into<String>>(
+        mut self,
+        endpoints: impl IntoIterator<Item = T>,
+    ) -> Self {
+        self.sampled = endpoints.into_iter().map(Into::into).collect();
+
+        self
+    }
+
+    /// The key of the given request, if its endpoint is cached and, for sampling endpoints, the request is deterministic.
+    ///
+    /// Requests differing in their URL, including its query, or in any header, like the credentials, never share a key.
+    pub(crate) fn key<X: Serialize>(
+        &self,
+        identifier: &str,
+        request: &Request,
+        body: &X,
+    ) -> Option<String> {
+        if !self.endpoints.iter().any(|e| e == identifier) {
+            return None;
+        }
+
+        let body = serde_json::to_value(body).ok()?;
+        let sampled = SAMPLING_ENDPOINTS.contains(&identifier)
+            && !self.sampled.iter().any(|e| e == identifier);
+        if sampled && !deterministic(&body) {
+            return None;
+        }
+
+        // Serializing through `Value` sorts the keys of the body.
+        let body = serde_json::to_vec(&body).ok()?;
+        let mut headers: Vec<_> = request.headers().iter().collect();
+        headers.sort_by(|a, b| (a.0.as_str(), a.1.as_bytes()).cmp(&(b.0.as_str(), b.1.as_bytes())));
+
+        let mut parts: Vec<&[u8]> = Vec::new();
+        for (name, value) in headers {
+            parts.extend([name.as_str().as_bytes(), b":", value.as_bytes(), b"\n"]);
+        }
+        parts.extend([request.url().as_str().as_bytes(), b"\n", &body]);
+
+        Some(format!("{:032x}", fnv1a(&parts)))
+    }
+
+    pub(crate) fn get(&self, key: &str) -> Option<Vec<u8>> {
+        self.store.get(key)
+    }
+
+    pub(crate) fn put(&self, key: &str, value: Vec<u8>) {
+        self.store.put(key, value)
+    }
+
+    /// Waits until no other request with the given key is in flight, and marks this one as in flight.
+    pub(crate) async fn coalesce(&self, key: &str) -> InFlight {
+        let lock = self
+            .in_flight
+            .lock()
+            .unwrap()
+            .entry(key.to_string())
+            .or_default()
+            .clone();
+
+        InFlight {
+            key: key.to_string(),
+            in_flight: self.in_flight.clone(),
+            guard: Some(lock.lock_owned().await),
+        }
+    }
+}
+
+impl fmt::Debug for Cache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Cache")
+            .field("endpoints", &self.endpoints)
+            .field("sampled", &self.sampled)
+            .finish()
+    }
+}
+
+/// Marks a request as in flight, until dropped.
+#[derive(Debug)]
+pub(crate) struct InFlight {
+    key: String,
+    in_flight: Arc<Mutex<HashMap<String, Arc<tokio::sync::Mutex<()>>>>>,
+    guard: Option<OwnedMutexGuard<()>>,
+}
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        let mut in_flight = self.in_flight.lock().unwrap();
+        self.guard.take();
+
+        // Forget the lock, unless other requests are waiting on it.
+        if in_flight
+            .get(&self.key)
+            .is_some_and(|lock| Arc::strong_count(lock) == 1)
+        {
+            in_flight.remove(&self.key);
+        }
+    }
+}
+
+/// Whether the request always gets the same reply, by not sampling or with a fixed `seed`.
+fn deterministic(body: &Value) -> bool {
+    let seeded = body.get("seed").is_some_and(|s| !s.is_null());
+    let greedy = body
+        .get("temperature")
+        .and_then(Value::as_f64)
+        .is_some_and(|t| t == 0.0);
+
+    seeded || greedy
+}
+
+/// The 128-bit FNV-1a hash of the given parts, stable across platforms & releases.
+pub(crate) fn fnv1a(parts: &[&[u8]]) -> u128 {
+    const OFFSET: u128 = 0x6c62272e07bb014262b821756295c58d;
+    const PRIME: u128 = 0x0000000001000000000000000000013b;
+
+    parts
+        .iter()
+        .flat_map(|p| p.iter())
+        .fold(OFFSET, |hash, &b| (hash ^ b as u128).wrapping_mul(PRIME))
+}
+
+/// An in-memory store, evicting the least recently used entries once full.
+#[derive(Debug, Default)]
+pub struct MemoryStore {
+    max_entries: Option<usize>,
+    max_bytes: Option<usize>,
+    ttl: Option<Duration>,
+    state: Mutex<Lru>,
+}
+
+#[derive(Debug, Default)]
+struct Lru {
+    entries: HashMap<String, Entry>,
+
+    /// Keys by the tick they were last used at, oldest first.
+    order: BTreeMap<u64, String>,
+    tick: u64,
+    bytes: usize,
+}
+
+#[derive(Debug)]
+struct Entry {
+    value: Vec<u8>,
+    stored_at: Instant,
+    used_at: u64,
+}
+
+impl MemoryStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn max_entries(mut self, max_entries: usize) -> Self {
+        self.max_entries = Some(max_entries);
+
+        self
+    }
+
+    /// The maximum size of every stored response combined.
+    pub fn max_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_bytes = Some(max_bytes);
+
+        self
+    }
+
+    /// How long responses are kept, forever by default.
+    pub fn ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+
+        self
+    }
+}
+
+impl CacheStore for MemoryStore {
+    fn get(&self, key: &str) -> Option<Vec<u8>> {
+        let mut lru = self.state.lock().unwrap();
+
+        let expired = lru
+            .entries
+            .get(key)
+            .map(|e| self.ttl.is_some_and(|ttl| e.stored_at.elapsed() >= ttl))?;
+        if expired {
+            lru.remove(key);
+            return None;
+        }
+
+        lru.tick += 1;
+        let tick = lru.tick;
+        let entry = lru.entries.get_mut(key)?;
+        let used_at = std::mem::replace(&mut entry.used_at, tick);
+        let value = entry.value.clone();
+
+        lru.order.remove(&used_at);
+        lru.order.insert(tick, key.to_string());
+
+        Some(value)
+    }
+
+    fn put(&self, key: &str, value: Vec<u8>) {
+        if self.max_bytes.is_some_and(|max| value.len() > max) || self.max_entries == Some(0) {
+            return;
+        }
+
+        let mut lru = self.state.lock().unwrap();
+        lru.remove(key);
+
+        while self.max_entries.is_some_and(|max| lru.entries.len() >= max)
+            || self
+                .max_bytes
+                .is_some_and(|max| lru.bytes + value.len() > max)
+        {
+            let Some((_, oldest)) = lru.order.pop_first() else {
+                break;
+            };
+            lru.remove(&oldest);
+        }
+
+        lru.tick += 1;
+        let tick = lru.tick;
+        lru.bytes += value.len();
+        lru.order.insert(tick, key.to_string());
+        lru.entries.insert(
+            key.to_string(),
+            Entry {
+                value,
+                stored_at: Instant::now(),
+                used_at: tick,
+            },
+        );
+    }
+}
+
+impl Lru {
+    fn remove(&mut self, key: &str) {
+        if let Some(entry) = self.entries.remove(key) {
+            self.order.remove(&entry.used_at);
+            self.bytes -= entry.value.len();
+        }
+    }
+}
+
+/// A store keeping each response in a file of the given directory, so that it outlives the process.
+///
+/// Once full, the oldest entries are evicted first. The entries are tracked in memory, from a single scan
+/// of the directory, so entries written by other processes in the meantime aren't evicted until the next scan.
+#[derive(Debug)]
+pub struct FileStore {
+    dir: PathBuf,
+    max_bytes: Option<u64>,
+    ttl: Option<Duration>,
+
+    /// The entries of the directory, scanned on the first write.
+    index: Mutex<Option<Index>>,
+}
+
+/// The entries of a [`FileStore`], oldest first.
+#[derive(Debug, Default)]
+struct Index {
+    entries: HashMap<String, (SystemTime, u64)>,
+    order: BTreeMap<(SystemTime, String), u64>,
+    bytes: u64,
+}
+
+impl FileStore {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            max_bytes: None,
+            ttl: None,
+            index: Mutex::default(),
+        }
+    }
+
+    /// The maximum size of every stored response combined.
+    pub fn max_bytes(mut self, max_bytes: u64) -> Self {
+        self.max_bytes = Some(max_bytes);
+
+        self
+    }
+
+    /// How long responses are kept, forever by default.
+    pub fn ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+
+        self
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{key}.json"))
+    }
+
+    /// Records a new entry, then removes the oldest ones until the directory fits in `max_bytes`.
+    fn evict(&self, key: &str, len: u64) -> std::io::Result<()> {
+        let Some(max_bytes) = self.max_bytes else {
+            return Ok(());
+        };
+
+        let mut index = self.index.lock().unwrap();
+        let index = match &mut *index {
+            Some(index) => index,
+            None => index.insert(Index::scan(&self.dir)?),
+        };
+        index.insert(key, SystemTime::now(), len);
+
+        while index.bytes > max_bytes {
+            let Some(((_, oldest), _)) = index.order.pop_first() else {
+                break;
+            };
+            index.remove(&oldest);
+
+            match fs::remove_file(self.path(&oldest)) {
+                Err(err) if err.kind() != std::io::ErrorKind::NotFound => return Err(err),
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Forgets an entry removed from the directory.
+    fn forget(&self, key: &str) {
+        if let Some(index) = &mut *self.index.lock().unwrap() {
+            index.remove(key);
+        }
+    }
+}
+
+impl Index {
+    /// Indexes the entries of the given directory, skipping the ones still being written.
+    fn scan(dir: &Path) -> std::io::Result<Self> {
+        let mut index = Self::default();
+
+        for entry in fs::read_dir(dir)? {
+            let entry = entry?;
+            let path = entry.path();
+            let meta = entry.metadata()?;
+            if !meta.is_file() || path.extension().map_or(true, |e| e != "json") {
+                continue;
+            }
+
+            if let Some(key) = path.file_stem().and_then(|k| k.to_str()) {
+                index.insert(key, meta.modified()?, meta.len());
+            }
+        }
+
+        Ok(index)
+    }
+
+    fn insert(&mut self, key: &str, modified: SystemTime, len: u64) {
+        self.remove(key);
+
+        self.entries.insert(key.to_string(), (modified, len));
+        self.order.insert((modified, key.to_string()), len);
+        self.bytes += len;
+    }
+
+    fn remove(&mut self, key: &str) {
+        if let Some((modified, len)) = self.entries.remove(key) {
+            self.order.remove(&(modified, key.to_string()));
+            self.bytes -= len;
+        }
+    }
+}
+
+impl CacheStore for FileStore {
+    fn get(&self, key: &str) -> Option<Vec<u8>> {
+        let path = self.path(key);
+
+        if let Some(ttl) = self.ttl {
+            let modified = fs::metadata(&path).and_then(|m| m.modified()).ok()?;
+            let age = SystemTime::now()
+                .duration_since(modified)
+                .unwrap_or_default();
+
+            if age >= ttl {
+                let _ = fs::remove_file(&path);
+                self.forget(key);
+                return None;
+            }
+        }
+
+        fs::read(path).ok()
+    }
+
+    fn put(&self, key: &str, value: Vec<u8>) {
+        let path = self.path(key);
+        let len = value.len() as u64;
+        // Written under another name first, so that readers never see a partial entry.
+        let tmp = path.with_extension(format!("tmp{}", fastrand::u32(..)));
+
+        let written = fs::create_dir_all(&self.dir)
+            .and_then(|_| fs::write(&tmp, value))
+            .and_then(|_| fs::rename(&tmp, &path));
+        if written.is_err() {
+            let _ = fs::remove_file(&tmp);
+            return;
+        }
+
+        let _ = self.evict(key, len);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use http::Method;
+    use serde_json::json;
+    use url::Url;
+
+    use super::*;
+    use crate::{
+        chat::{chat, chat_with},
+        embedding,
+        meta::with_meta,
+        testing::MockTransport,
+        types::{ChatMessageBuilder, ChatParamBuilder, EmbeddingParamBuilder},
+        RequestOptions,
+    };
+
+    #[test]
+    fn test_memory_store_lru() {
+        let store = MemoryStore::new().max_entries(2);
+
+        store.put("a", b"1".to_vec());
+        store.put("b", b"2".to_vec());
+        assert_eq!(store.get("a"), Some(b"1".to_vec()));
+
+        // "b" is now the least recently used.
+        store.put("c", b"3".to_vec());
+        assert_eq!(store.get("b"), None);
+        assert_eq!(store.get("a"), Some(b"1".to_vec()));
+        assert_eq!(store.get("c"), Some(b"3".to_vec()));
+
+        let store = MemoryStore::new().max_bytes(4);
+        store.put("a", b"12".to_vec());
+        store.put("b", b"34".to_vec());
+        store.put("c", b"5".to_vec());
+        assert_eq!(store.get("a"), None);
+        assert_eq!(store.get("b"), Some(b"34".to_vec()));
+
+        let store = MemoryStore::new().ttl(Duration::ZERO);
+        store.put("a", b"1".to_vec());
+        assert_eq!(store.get("a"), None);
+    }
+
+    #[test]
+    fn test_file_store() {
+        let dir = std::env::temp_dir().join(format!("fieri-cache-{}", fastrand::u64(..)));
+        let store = FileStore::new(&dir).max_bytes(4);
+
+        store.put("a", b"12".to_vec());
+        std::thread::sleep(Duration::from_millis(10));
+        store.put("b", b"34".to_vec());
+        assert_eq!(store.get("a"), Some(b"12".to_vec()));
+
+        // "a" is the oldest entry.
+        std::thread::sleep(Duration::from_millis(10));
+        store.put("c", b"5".to_vec());
+        assert_eq!(store.get("a"), None);
+        assert_eq!(store.get("b"), Some(b"34".to_vec()));
+
+        assert_eq!(FileStore::new(&dir).ttl(Duration::ZERO).get("c"), None);
+        assert_eq!(store.get("c"), None);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_file_store_skips_partial_entries() {
+        let dir = std::env::temp_dir().join(format!("fieri-cache-{}", fastrand::u64(..)));
+        fs::create_dir_all(&dir).unwrap();
+        // Left behind by a concurrent write, or a process that died mid-write.
+        fs::write(dir.join("a.tmp123"), b"123456").unwrap();
+
+        let store = FileStore::new(&dir).max_bytes(4);
+        store.put("b", b"12".to_vec());
+        store.put("c", b"34".to_vec());
+
+        assert!(dir.join("a.tmp123").exists());
+        assert_eq!(store.get("b"), Some(b"12".to_vec()));
+        assert_eq!(store.get("c"), Some(b"34".to_vec()));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_key() {
+        let cache = Cache::new(MemoryStore::new());
+        let url = Url::parse("https://api.openai.com/v1/chat/completions").unwrap();
+        let request = |headers: &[(&'static str, &'static str)]| {
+            let mut request = Request::new(Method::POST, url.clone());
+            for (name, value) in headers {
+                request.headers_mut().insert(*name, value.parse().unwrap());
+            }
+
+            request
+        };
+        let key = |body: Value, request: &Request| cache.key("chat/completions", request, &body);
+        let a = request(&[("authorization", "Bearer sk-a")]);
+
+        // Sampled chats would always get the same reply.
+        assert_eq!(key(json!({"model": "gpt-4o"}), &a), None);
+        assert_eq!(key(json!({"temperature": 0.7}), &a), None);
+        assert!(key(json!({"temperature": 0}), &a).is_some());
+        assert!(key(json!({"seed": 42}), &a).is_some());
+
+        let b = request(&[("authorization", "Bearer sk-b")]);
+        assert_ne!(key(json!({"seed": 42}), &a), key(json!({"seed": 42}), &b));
+
+        let beta = request(&[
+            ("openai-beta", "assistants=v2"),
+            ("authorization", "Bearer sk-a"),
+        ]);
+        assert_ne!(
+            key(json!({"seed": 42}), &a),
+            key(json!({"seed": 42}), &beta)
+        );
+
+        let mut query = request(&[("authorization", "Bearer sk-a")]);
+        query.url_mut().set_query(Some("api-version=beta"));
+        assert_ne!(
+            key(json!({"seed": 42}), &a),
+            key(json!({"seed": 42}), &query)
+        );
+
+        let cache = Cache::new(MemoryStore::new()).cache_sampled(["chat/completions"]);
+        assert!(cache.key("chat/completions", &a, &json!({})).is_some());
+    }
+
+    #[tokio::test]
+    async fn test_client_cache() {
+        let mock = MockTransport::with_fixtures();
+        let client = mock.client().cache(Cache::new(MemoryStore::new()));
+
+        let message = ChatMessageBuilder::new("user", "Hello!").build().unwrap();
+        let param = ChatParamBuilder::new("gpt-3.5-turbo", vec![message])
+            .seed(42_u64)
+            .build()
+            .unwrap();
+
+        // Concurrent requests are coalesced, & later ones hit the cache.
+        let resps = futures::future::join_all((0..5).map(|_| chat(&client, &param))).await;
+        let resp = with_meta(chat(&client.clone(), &param)).await.unwrap();
+
+        assert!(resps.iter().all(|r| r.is_ok()));
+        assert_eq!(resp.value.usage.total_tokens, 18);
+        assert!(resp.meta.cached);
+        assert_eq!(mock.requests().len(), 1);
+
+        let message = ChatMessageBuilder::new("user", "Hello!").build().unwrap();
+        let sampled = ChatParamBuilder::new("gpt-3.5-turbo", vec![message])
+            .build()
+            .unwrap();
+        chat(&client, &sampled).await.unwrap();
+        chat(&client, &sampled).await.unwrap();
+        assert_eq!(mock.requests().len(), 3);
+
+        // Options changing the request change its key.
+        let options = RequestOptions::new().query("api-version", "beta");
+        chat_with(&client, &param, options.clone()).await.unwrap();
+        chat_with(&client, &param, options).await.unwrap();
+        let options = RequestOptions::new().header("OpenAI-Beta", "assistants=v2");
+        chat_with(&client, &param, options).await.unwrap();
+        assert_eq!(mock.requests().len(), 5);
+
+        let param = EmbeddingParamBuilder::new("text-embedding-ada-002", "Hello!")
+            .build()
+            .unwrap();
+        embedding::create(&client, &param).await.unwrap();
+        embedding::create(&client, &param).await.unwrap();
+        assert_eq!(mock.requests().len(), 6);
+    }
+}
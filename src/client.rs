@@ -16,25 +16,65 @@
 //!     .api_key("...")
 //!     .organization("...");
 //! ```
+//!
+//! ## Usage with a custom endpoint, timeouts & proxy
+//! ```no_run
+//! use std::time::Duration;
+//! use fieri::Client;
+//!
+//! # fn main() -> Result<(), fieri::Error> {
+//! let client = Client::builder()
+//!     .base_url("http://localhost:8080/v1/")
+//!     .timeout(Duration::from_secs(120))
+//!     .connect_timeout(Duration::from_secs(5))
+//!     .proxy("http://proxy.internal:3128")
+//!     .header("X-Team", "research")
+//!     .build()?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! ## Tracing
+//! With the `tracing` feature, each request is instrumented with a `gen_ai` span, attributed after the
+//! [OpenTelemetry GenAI semantic conventions](https://opentelemetry.io/docs/specs/semconv/gen-ai/):
+//! the requested & responding model, the endpoint, status, retries, request ID, token usage
+//! and, for streams, the time to the first event.
+//! Prompts & completions are only recorded when enabled with `Client::trace_content`.
 
-use std::fmt::Debug;
+use std::{fmt::Debug, sync::Arc, time::Duration};
 
-use reqwest::{
-    header::{HeaderMap, AUTHORIZATION},
-    multipart,
+use futures::stream::BoxStream;
+use http::{
+    header::{self, HeaderMap, HeaderName, HeaderValue},
+    Method,
 };
+use reqwest::{Certificate, Proxy};
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+use url::Url;
 
 use crate::{
+    azure::AzureConfig,
+    cache::Cache,
     config::Config,
     error::{Error, RequestError},
+    meta,
+    metrics::{MetricsSink, RequestMetrics, Sink},
+    middleware::{Middleware, Next, Stack},
+    multipart::Form,
+    options::{Options, RequestOptions},
+    rate_limit::{self, Permit, RateLimiter},
+    retry::RetryPolicy,
+    sse,
+    trace::Trace,
+    transport::{self, Custom, Request, Transport},
     Result,
 };
 
 // Response returned by each interaction with OpenAI, either an error or a valid generic.
 #[derive(Debug, Deserialize)]
 #[serde(untagged)]
-enum Response<T> {
+pub(crate) enum Response<T> {
     Invalid(RequestError),
     Valid(T),
 }
@@ -45,71 +85,58 @@ pub struct Client {
     /// Configuration needed to authorize against the API.
     config: Config,
 
-    /// The HTTP client that'll execute requests.
+    /// The HTTP client executing requests, unless a custom transport is set.
     handler: reqwest::Client,
+
+    /// The transport executing requests in place of `handler`.
+    transport: Option<Custom>,
+
+    /// When and how failed requests are retried, if at all.
+    retry: Option<RetryPolicy>,
+
+    /// Delays requests that would exceed the rate limits, shared between clones.
+    rate_limiter: Option<RateLimiter>,
+
+    /// Hooks around each request, in order.
+    middleware: Stack,
+
+    /// Answers identical requests without sending them, shared between clones.
+    cache: Option<Cache>,
+
+    /// Whether spans record the content of prompts & completions.
+    trace_content: bool,
+
+    /// Receives the metrics of each request.
+    metrics: Option<Sink>,
+
+    /// Applied to each request, over the defaults above.
+    options: Options,
 }
 
 impl Client {
     /// Creates a new instance of the Client.
     /// The API key is read from the `OPENAI_API_KEY` environment variable.
     /// The API Organization is read from the `OPENAI_ORGANIZATION` environment variable.
+    ///
+    /// Use [`Client::builder`] to handle errors instead of panicking, or to customize the underlying connection.
     pub fn new() -> Self {
-        let mut headers = HeaderMap::new();
-
-        let api_key = std::env::var("OPENAI_API_KEY").unwrap_or_else(|_| "".to_string());
-        let organization = std::env::var("OPENAI_ORGANIZATION").unwrap_or_else(|_| "".to_string());
-
-        if !api_key.is_empty() {
-            headers.insert(
-                AUTHORIZATION,
-                format!("Bearer {api_key}")
-                    .parse()
-                    .expect("Unable to parse the API key."),
-            );
-        }
-
-        if !organization.is_empty() {
-            headers.insert(
-                "OpenAI-Organization",
-                organization
-                    .parse()
-                    .expect("Unable to parse the given Organization."),
-            );
-        }
+        ClientBuilder::new()
+            .build()
+            .expect("Err creating request handler.")
+    }
 
-        let config = Config::new(api_key).headers(headers.clone());
-        Self {
-            config,
-            handler: reqwest::Client::builder()
-                .default_headers(headers)
-                .build()
-                .expect("Err creating request handler."),
-        }
+    /// Creates a [`ClientBuilder`] to configure a Client.
+    pub fn builder() -> ClientBuilder {
+        ClientBuilder::new()
     }
 
     /// Explicitly specify the api key.
     /// By default, the api key is read from the `OPENAI_API_KEY` environment variable.
     /// If both `OPENAI_API_KEY` and `api_key` are set, the `api_key` takes precedence.
     pub fn api_key<T: Into<String>>(mut self, api_key: T) -> Self {
-        let api_key = api_key.into();
-        let mut headers = self.config.headers;
-        headers.insert(
-            AUTHORIZATION,
-            format!("Bearer {api_key}")
-                .parse()
-                .expect("Unable to parse the API key."),
-        );
+        self.config.api_key = api_key.into();
 
-        self.config.api_key = api_key;
-        self.config.headers = headers.clone();
-
-        Self {
-            config: self.config,
-            handler: reqwest::Client::builder()
-                .default_headers(headers)
-                .build()
-                .expect("Err creating request handler."),
-        }
+        self
     }
 
     /// For users who belong to multiple organizations, you can pass a header
@@ -117,25 +144,98 @@ impl Client {
     /// By default, the organization is read from the `OPENAI_ORGANIZATION` environment variable.
     /// If both `OPENAI_ORGANIZATION` and `organization` are set, the `organization` takes precedence.
     pub fn organization<T: Into<String>>(mut self, organization: T) -> Self {
-        let organization = organization.into();
-        let mut headers = self.config.headers;
-        headers.insert(
-            "OpenAI-Organization",
-            organization
-                .parse()
-                .expect("Unable to parse the given Organization."),
-        );
+        self.config.organization = organization.into();
+
+        self
+    }
 
-        self.config.organization = organization;
-        self.config.headers = headers.clone();
+    /// Retry requests that failed for transient reasons, according to the given policy.
+    ///
+    /// By default, each request is attempted only once.
+    pub fn retry(mut self, policy: RetryPolicy) -> Self {
+        self.retry = Some(policy);
 
-        Self {
-            config: self.config,
-            handler: reqwest::Client::builder()
-                .default_headers(headers)
-                .build()
-                .expect("Err creating a request handler."),
-        }
+        self
+    }
+
+    /// Delay requests that would exceed the limits of the given [`RateLimiter`].
+    ///
+    /// By default, requests are sent right away.
+    pub fn rate_limiter(mut self, limiter: RateLimiter) -> Self {
+        self.rate_limiter = Some(limiter);
+
+        self
+    }
+
+    /// Answer identical requests to the endpoints of the given [`Cache`] from it.
+    ///
+    /// By default, nothing is cached.
+    pub fn cache(mut self, cache: Cache) -> Self {
+        self.cache = Some(cache);
+
+        self
+    }
+
+    /// Send requests through the given [`Transport`], instead of the default [`reqwest::Client`].
+    ///
+    /// Settings of the connection, like timeouts or proxies, only apply to the default transport.
+    pub fn transport(mut self, transport: impl Transport) -> Self {
+        self.transport = Some(Custom(Arc::new(transport)));
+
+        self
+    }
+
+    /// Add a [`Middleware`] around each request.
+    ///
+    /// Middleware runs in the order it was added, so the first one sees each request first, and its response last.
+    pub fn middleware(mut self, middleware: impl Middleware) -> Self {
+        self.middleware.0.push(Arc::new(middleware));
+
+        self
+    }
+
+    /// Report the [`RequestMetrics`](crate::metrics::RequestMetrics) of each request to the given sink.
+    ///
+    /// Streams are reported once they end, or are dropped.
+    pub fn metrics(mut self, sink: impl MetricsSink) -> Self {
+        self.metrics = Some(Sink(Arc::new(sink)));
+
+        self
+    }
+
+    /// Also report the metrics of each request to the given sink, after the one set by [`metrics`](Self::metrics), if any.
+    pub(crate) fn observe(mut self, sink: impl MetricsSink) -> Self {
+        let sink: Arc<dyn MetricsSink> = Arc::new(sink);
+        let sink = match self.metrics.take() {
+            Some(Sink(previous)) => Arc::new(move |metrics: &RequestMetrics| {
+                previous.record(metrics);
+                sink.record(metrics);
+            }),
+            None => sink,
+        };
+        self.metrics = Some(Sink(sink));
+
+        self
+    }
+
+    /// Record the content of prompts & completions in the span of each request.
+    ///
+    /// By default, only their metadata is recorded, since the content may be sensitive.
+    #[cfg(feature = "tracing")]
+    pub fn trace_content(mut self, enabled: bool) -> Self {
+        self.trace_content = enabled;
+
+        self
+    }
+
+    /// A clone of the client, applying the given options to each of its requests.
+    ///
+    /// Options are merged over any options of this client.
+    pub fn with_options(&self, options: RequestOptions) -> Result<Self> {
+        Ok(Self {
+            options: self.options.merge(options)?,
+            ..self.clone()
+        })
     }
 
     pub async fn get<X, Y>(&self, identifier: &str, param: Option<&X>) -> Result<Y>
@@ -143,37 +243,41 @@ impl Client {
         X: Serialize,
         Y: DeserializeOwned,
     {
-        let resp = self
-            .handler
-            .get(self.config.url.join(identifier)?)
-            .query(&param)
-            .send()
-            .await?
-            .json::<Response<Y>>()
-            .await?;
+        let url = self.url::<()>(identifier, None)?;
+        let trace = self.trace::<()>("GET", identifier, &url, None);
 
-        match resp {
-            Response::Invalid(resp) => Err(Error::APIError(resp)),
-            Response::Valid(resp) => Ok(resp),
-        }
+        trace
+            .run(async {
+                let resp = self
+                    .send(&trace, || query(Method::GET, &url, param))
+                    .await?;
+
+                decode(resp, &trace).await
+            })
+            .await
     }
 
-    pub async fn get_stream<X>(
+    pub async fn get_stream<X, Y>(
         &self,
         identifier: &str,
         param: Option<&X>,
-    ) -> Result<reqwest::Response>
+    ) -> Result<BoxStream<'static, Result<Y>>>
     where
         X: Serialize,
+        Y: DeserializeOwned + Send + 'static,
     {
-        let resp = self
-            .handler
-            .get(self.config.url.join(identifier)?)
-            .query(&param)
-            .send()
-            .await?;
+        let url = self.url::<()>(identifier, None)?;
+        let trace = self.trace::<()>("GET", identifier, &url, None);
+
+        trace
+            .run(async {
+                let resp = self
+                    .send(&trace, || query(Method::GET, &url, param))
+                    .await?;
 
-        Ok(resp)
+                Ok(sse::stream(resp, trace.clone()))
+            })
+            .await
     }
 
     pub async fn post<X, Y>(&self, identifier: &str, param: Option<&X>) -> Result<Y>
@@ -181,56 +285,163 @@ impl Client {
         X: Serialize,
         Y: DeserializeOwned,
     {
-        let resp = self
-            .handler
-            .post(self.config.url.join(identifier)?)
-            .json(&param)
-            .send()
-            .await?
-            .json::<Response<Y>>()
-            .await?;
-
-        match resp {
-            Response::Invalid(resp) => Err(Error::APIError(resp)),
-            Response::Valid(resp) => Ok(resp),
+        match self.options.extend(param)? {
+            Some(body) => self.post_json(identifier, Some(&body)).await,
+            None => self.post_json(identifier, param).await,
         }
     }
 
-    pub async fn post_stream<X>(
+    async fn post_json<X, Y>(&self, identifier: &str, param: Option<&X>) -> Result<Y>
+    where
+        X: Serialize,
+        Y: DeserializeOwned,
+    {
+        let url = self.url(identifier, param)?;
+        let trace = self.trace("POST", identifier, &url, param);
+
+        trace
+            .run(self.post_cached(identifier, &url, param, &trace))
+            .await
+    }
+
+    /// Posts the given body, answering it from the [`Cache`] if possible.
+    async fn post_cached<X, Y>(
         &self,
         identifier: &str,
+        url: &Url,
         param: Option<&X>,
-    ) -> Result<reqwest::Response>
+        trace: &Trace,
+    ) -> Result<Y>
     where
         X: Serialize,
+        Y: DeserializeOwned,
     {
-        let resp = self
-            .handler
-            .post(self.config.url.join(identifier)?)
-            .json(&param)
-            .send()
-            .await?;
+        let cached = match self.cache.as_ref().zip(param) {
+            Some((cache, param)) => {
+                // Keyed on the request as sent, with the query & headers of its options.
+                let request = self.options.apply(self.authorize(json(url, Some(param))?)?);
+                cache
+                    .key(identifier, &request, param)
+                    .map(|key| (cache, key))
+            }
+            None => None,
+        };
+        let Some((cache, key)) = cached else {
+            return parse(&self.fetch(url, param, trace).await?);
+        };
+
+        // Identical requests wait for the one in flight, then find its response in the cache.
+        let _in_flight = cache.coalesce(&key).await;
+        if let Some(body) = cache.get(&key) {
+            trace.cache_hit();
+            trace.body(&body);
+            meta::record_cached();
+
+            return parse(&body);
+        }
+
+        let body = self.fetch(url, param, trace).await?;
+        let value = parse(&body)?;
+        cache.put(&key, body);
+
+        Ok(value)
+    }
 
-        Ok(resp)
+    /// Posts the given body, returning the body of the response.
+    async fn fetch<X: Serialize>(
+        &self,
+        url: &Url,
+        param: Option<&X>,
+        trace: &Trace,
+    ) -> Result<Vec<u8>> {
+        let permit = self.acquire(param).await;
+        let resp = match self.send(trace, || json(url, param)).await {
+            Ok(resp) => resp,
+            Err(err) => {
+                refund_unsent(permit, &err);
+                return Err(err);
+            }
+        };
+
+        let body = resp.bytes().await?.to_vec();
+        trace.body(&body);
+        if let Some(permit) = permit {
+            permit.settle(&body);
+        }
+
+        Ok(body)
+    }
+
+    pub async fn post_stream<X, Y>(
+        &self,
+        identifier: &str,
+        param: Option<&X>,
+    ) -> Result<BoxStream<'static, Result<Y>>>
+    where
+        X: Serialize,
+        Y: DeserializeOwned + Send + 'static,
+    {
+        match self.options.extend(param)? {
+            Some(body) => self.post_stream_json(identifier, Some(&body)).await,
+            None => self.post_stream_json(identifier, param).await,
+        }
     }
 
-    pub async fn post_data<Y>(&self, identifier: &str, data: multipart::Form) -> Result<Y>
+    async fn post_stream_json<X, Y>(
+        &self,
+        identifier: &str,
+        param: Option<&X>,
+    ) -> Result<BoxStream<'static, Result<Y>>>
     where
-        Y: DeserializeOwned,
+        X: Serialize,
+        Y: DeserializeOwned + Send + 'static,
     {
-        let resp = self
-            .handler
-            .post(self.config.url.join(identifier)?)
-            .multipart(data)
-            .send()
-            .await?
-            .json::<Response<Y>>()
-            .await?;
+        let url = self.url(identifier, param)?;
+        let trace = self.trace("POST", identifier, &url, param);
 
-        match resp {
-            Response::Invalid(resp) => Err(Error::APIError(resp)),
-            Response::Valid(resp) => Ok(resp),
-        }
+        trace
+            .run(async {
+                // The usage of streams is rarely known, so their estimate is only corrected when they're never sent.
+                let permit = self.acquire(param).await;
+                let resp = self
+                    .send(&trace, || json(&url, param))
+                    .await
+                    .map_err(|err| {
+                        refund_unsent(permit, &err);
+                        err
+                    })?;
+
+                Ok(sse::stream(resp, trace.clone()))
+            })
+            .await
+    }
+
+    /// Sends a multipart form.
+    pub async fn post_data<Y: DeserializeOwned>(&self, identifier: &str, form: Form) -> Result<Y> {
+        let url = self.url::<()>(identifier, None)?;
+        let trace = self.trace::<()>("POST", identifier, &url, None);
+
+        let form = self.options.extend_form(form);
+        let body = form.encode();
+        let content_type = HeaderValue::try_from(form.content_type())?;
+
+        trace
+            .run(async {
+                let resp = self
+                    .send(&trace, || {
+                        let mut request = Request::new(Method::POST, url.clone());
+                        request
+                            .headers_mut()
+                            .insert(header::CONTENT_TYPE, content_type.clone());
+                        *request.body_mut() = body.clone().into();
+
+                        Ok(request)
+                    })
+                    .await?;
+
+                decode(resp, &trace).await
+            })
+            .await
     }
 
     pub async fn delete<X, Y>(&self, identifier: &str, param: Option<&X>) -> Result<Y>
@@ -238,18 +449,483 @@ impl Client {
         X: Serialize,
         Y: DeserializeOwned,
     {
-        let resp = self
-            .handler
-            .delete(self.config.url.join(identifier)?)
-            .query(&param)
-            .send()
-            .await?
-            .json::<Response<Y>>()
-            .await?;
-
-        match resp {
-            Response::Invalid(resp) => Err(Error::APIError(resp)),
-            Response::Valid(resp) => Ok(resp),
+        let url = self.url::<()>(identifier, None)?;
+        let trace = self.trace::<()>("DELETE", identifier, &url, None);
+
+        trace
+            .run(async {
+                let resp = self
+                    .send(&trace, || query(Method::DELETE, &url, param))
+                    .await?;
+
+                decode(resp, &trace).await
+            })
+            .await
+    }
+
+    /// Sends the request built by `request`, building it again for each retry allowed by the [`RetryPolicy`].
+    async fn send<F>(&self, trace: &Trace, request: F) -> Result<transport::Response>
+    where
+        F: Fn() -> Result<Request>,
+    {
+        let mut attempt = 1;
+
+        loop {
+            let request = self.options.apply(self.authorize(request()?)?);
+            let outcome = Next::new(self.sender(), &self.middleware.0)
+                .run(request)
+                .await;
+
+            match self.retry.as_ref().and_then(|p| p.delay(attempt, &outcome)) {
+                Some(delay) => tokio::time::sleep(delay).await,
+                None => {
+                    let resp = outcome?;
+                    meta::record(resp.headers());
+                    trace.response(attempt, resp.status().as_u16(), resp.headers());
+
+                    return check(resp).await;
+                }
+            }
+
+            attempt += 1;
+        }
+    }
+
+    /// Waits until the [`RateLimiter`], if any, allows sending the given request.
+    async fn acquire<X: Serialize>(&self, body: Option<&X>) -> Option<Permit> {
+        let limiter = self.rate_limiter.as_ref()?;
+        let body = serde_json::to_value(body?).ok()?;
+        let model = body.get("model")?.as_str()?;
+
+        Some(limiter.acquire(model, rate_limit::estimate(&body)).await)
+    }
+
+    /// Starts the span of a request to the given endpoint.
+    fn trace<X: Serialize>(
+        &self,
+        method: &'static str,
+        identifier: &str,
+        url: &Url,
+        body: Option<&X>,
+    ) -> Trace {
+        Trace::new(
+            method,
+            identifier,
+            url,
+            body,
+            self.trace_content,
+            self.metrics.as_ref(),
+        )
+    }
+
+    /// The transport executing requests.
+    fn sender(&self) -> &dyn Transport {
+        match &self.transport {
+            Some(Custom(transport)) => transport.as_ref(),
+            None => &self.handler,
+        }
+    }
+
+    /// The full URL of the given endpoint.
+    ///
+    /// For Azure, the `model` of the request body decides which deployment serves the request.
+    fn url<X: Serialize>(&self, identifier: &str, body: Option<&X>) -> Result<Url> {
+        let Some(azure) = &self.config.azure else {
+            return Ok(self.config.url.join(identifier)?);
+        };
+
+        let model = body
+            .and_then(|b| serde_json::to_value(b).ok())
+            .and_then(|b| Some(b.get("model")?.as_str()?.to_string()));
+
+        azure.url(&self.config.url, identifier, model.as_deref())
+    }
+
+    /// Attaches the credentials to the given request.
+    fn authorize(&self, mut request: Request) -> Result<Request> {
+        // Also set by `handler` when sending, but custom transports & middleware should see them too.
+        let headers = request.headers_mut();
+        for (name, value) in &self.config.headers {
+            headers.insert(name, value.clone());
+        }
+
+        let secret = |value: String| -> Result<HeaderValue> {
+            let mut value = HeaderValue::try_from(value)?;
+            value.set_sensitive(true);
+
+            Ok(value)
+        };
+
+        if self.config.azure.is_some() {
+            if !self.config.api_key.is_empty() {
+                headers.insert("api-key", secret(self.config.api_key.clone())?);
+            }
+
+            return Ok(request);
+        }
+
+        if !self.config.api_key.is_empty() {
+            let bearer = secret(format!("Bearer {}", self.config.api_key))?;
+            headers.insert(header::AUTHORIZATION, bearer);
+        }
+
+        if !self.config.organization.is_empty() {
+            let organization = HeaderValue::try_from(self.config.organization.as_str())?;
+            headers.insert("OpenAI-Organization", organization);
+        }
+
+        Ok(request)
+    }
+}
+
+/// A request without a body, with `param` serialized into the query string.
+fn query<X: Serialize>(method: Method, url: &Url, param: Option<&X>) -> Result<Request> {
+    let mut url = url.clone();
+
+    if let Some(Value::Object(fields)) = param.map(serde_json::to_value).transpose()? {
+        let mut pairs = url.query_pairs_mut();
+        for (name, value) in fields {
+            match value {
+                Value::Null => continue,
+                Value::String(value) => pairs.append_pair(&name, &value),
+                value => pairs.append_pair(&name, &value.to_string()),
+            };
+        }
+    }
+
+    Ok(Request::new(method, url))
+}
+
+/// A `POST` request with `param` as its JSON body.
+fn json<X: Serialize>(url: &Url, param: Option<&X>) -> Result<Request> {
+    let mut request = Request::new(Method::POST, url.clone());
+    request.headers_mut().insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("application/json"),
+    );
+    *request.body_mut() = serde_json::to_vec(&param)?.into();
+
+    Ok(request)
+}
+
+/// Gives back the permit of a request that failed before reaching OpenAI.
+fn refund_unsent(permit: Option<Permit>, err: &Error) {
+    if let Some(permit) = permit.filter(|_| rate_limit::unsent(err)) {
+        permit.refund();
+    }
+}
+
+/// Turns unsuccessful responses into the matching [`Error`].
+async fn check(resp: transport::Response) -> Result<transport::Response> {
+    if resp.status().is_success() {
+        return Ok(resp);
+    }
+
+    let status = resp.status().as_u16();
+    let headers = resp.headers().clone();
+    let body = resp.text().await?;
+
+    Err(Error::from_response(status, &headers, body))
+}
+
+/// Decodes a successful response, which may still contain an error.
+async fn decode<Y: DeserializeOwned>(resp: transport::Response, trace: &Trace) -> Result<Y> {
+    let body = resp.bytes().await?;
+    trace.body(&body);
+
+    parse(&body)
+}
+
+fn parse<Y: DeserializeOwned>(body: &[u8]) -> Result<Y> {
+    meta::record_body(body);
+
+    match serde_json::from_slice::<Response<Y>>(body)? {
+        Response::Invalid(resp) => Err(Error::APIError(resp)),
+        Response::Valid(resp) => Ok(resp),
+    }
+}
+
+/// A builder to configure a [`Client`], and the connection it uses.
+///
+/// Unlike [`Client::new`], building never panics; invalid values are reported as an [`Error`].
+#[derive(Debug, Default)]
+pub struct ClientBuilder {
+    api_key: Option<String>,
+    organization: Option<String>,
+    base_url: Option<String>,
+    headers: Vec<(String, String)>,
+    user_agent: Option<String>,
+    timeout: Option<Duration>,
+    connect_timeout: Option<Duration>,
+    proxy: Option<String>,
+    root_certificates: Vec<Vec<u8>>,
+    accept_invalid_certs: bool,
+    pool_idle_timeout: Option<Duration>,
+    pool_max_idle_per_host: Option<usize>,
+    retry: Option<RetryPolicy>,
+    rate_limiter: Option<RateLimiter>,
+    middleware: Stack,
+    transport: Option<Custom>,
+    cache: Option<Cache>,
+    trace_content: bool,
+    metrics: Option<Sink>,
+    azure: Option<AzureConfig>,
+}
+
+impl ClientBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The API key, read from the `OPENAI_API_KEY` environment variable if not set.
+    pub fn api_key(mut self, api_key: impl Into<String>) -> Self {
+        self.api_key = Some(api_key.into());
+
+        self
+    }
+
+    /// The organization, read from the `OPENAI_ORGANIZATION` environment variable if not set.
+    pub fn organization(mut self, organization: impl Into<String>) -> Self {
+        self.organization = Some(organization.into());
+
+        self
+    }
+
+    /// The URL the endpoints are relative to, `https://api.openai.com/v1/` by default.
+    ///
+    /// Useful for gateways, proxies and OpenAI-compatible servers.
+    pub fn base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = Some(base_url.into());
+
+        self
+    }
+
+    /// A header sent with each request.
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+
+        self
+    }
+
+    pub fn user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = Some(user_agent.into());
+
+        self
+    }
+
+    /// The timeout of each request, from connecting until the response body has been read.
+    ///
+    /// For streams, the timeout includes reading the whole stream.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+
+        self
+    }
+
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+
+        self
+    }
+
+    /// Route every request through the proxy at the given URL.
+    pub fn proxy(mut self, proxy: impl Into<String>) -> Self {
+        self.proxy = Some(proxy.into());
+
+        self
+    }
+
+    /// Trust an additional, PEM encoded, root certificate.
+    pub fn add_root_certificate(mut self, pem: impl Into<Vec<u8>>) -> Self {
+        self.root_certificates.push(pem.into());
+
+        self
+    }
+
+    /// Accept any TLS certificate, including expired & self-signed ones.
+    ///
+    /// Only meant for testing against local servers.
+    pub fn danger_accept_invalid_certs(mut self, accept: bool) -> Self {
+        self.accept_invalid_certs = accept;
+
+        self
+    }
+
+    /// How long idle connections are kept in the pool.
+    pub fn pool_idle_timeout(mut self, timeout: Duration) -> Self {
+        self.pool_idle_timeout = Some(timeout);
+
+        self
+    }
+
+    pub fn pool_max_idle_per_host(mut self, max: usize) -> Self {
+        self.pool_max_idle_per_host = Some(max);
+
+        self
+    }
+
+    /// See [`Client::retry`].
+    pub fn retry(mut self, policy: RetryPolicy) -> Self {
+        self.retry = Some(policy);
+
+        self
+    }
+
+    /// See [`Client::rate_limiter`].
+    pub fn rate_limiter(mut self, limiter: RateLimiter) -> Self {
+        self.rate_limiter = Some(limiter);
+
+        self
+    }
+
+    /// See [`Client::cache`].
+    pub fn cache(mut self, cache: Cache) -> Self {
+        self.cache = Some(cache);
+
+        self
+    }
+
+    /// See [`Client::transport`].
+    pub fn transport(mut self, transport: impl Transport) -> Self {
+        self.transport = Some(Custom(Arc::new(transport)));
+
+        self
+    }
+
+    /// See [`Client::middleware`].
+    pub fn middleware(mut self, middleware: impl Middleware) -> Self {
+        self.middleware.0.push(Arc::new(middleware));
+
+        self
+    }
+
+    /// See [`Client::metrics`].
+    pub fn metrics(mut self, sink: impl MetricsSink) -> Self {
+        self.metrics = Some(Sink(Arc::new(sink)));
+
+        self
+    }
+
+    /// See [`Client::trace_content`].
+    #[cfg(feature = "tracing")]
+    pub fn trace_content(mut self, enabled: bool) -> Self {
+        self.trace_content = enabled;
+
+        self
+    }
+
+    /// Talk to an [Azure OpenAI](crate::azure) resource, instead of OpenAI.
+    ///
+    /// The endpoint of the resource takes precedence over [`base_url`](Self::base_url).
+    pub fn azure(mut self, azure: AzureConfig) -> Self {
+        self.azure = Some(azure);
+
+        self
+    }
+
+    pub fn build(self) -> Result<Client> {
+        let mut config = Config::new(
+            self.api_key
+                .or_else(|| std::env::var("OPENAI_API_KEY").ok())
+                .unwrap_or_default(),
+        );
+        config.organization = self
+            .organization
+            .or_else(|| std::env::var("OPENAI_ORGANIZATION").ok())
+            .unwrap_or_default();
+
+        if let Some(base_url) = self
+            .azure
+            .as_ref()
+            .map(|a| a.endpoint.clone())
+            .or(self.base_url)
+        {
+            // Endpoints are joined to the base URL, which would otherwise drop its last segment.
+            let base_url = match base_url.ends_with('/') {
+                true => base_url,
+                false => format!("{base_url}/"),
+            };
+            config.url = Url::parse(&base_url)?;
+        }
+        config.azure = self.azure;
+
+        let mut headers = HeaderMap::new();
+        for (name, value) in self.headers {
+            headers.insert(HeaderName::try_from(name)?, HeaderValue::try_from(value)?);
+        }
+        config = config.headers(headers.clone());
+
+        let mut handler = reqwest::Client::builder()
+            .default_headers(headers)
+            .danger_accept_invalid_certs(self.accept_invalid_certs);
+
+        if let Some(user_agent) = self.user_agent {
+            handler = handler.user_agent(user_agent);
+        }
+        if let Some(timeout) = self.timeout {
+            handler = handler.timeout(timeout);
         }
+        if let Some(timeout) = self.connect_timeout {
+            handler = handler.connect_timeout(timeout);
+        }
+        if let Some(proxy) = self.proxy {
+            handler = handler.proxy(Proxy::all(proxy)?);
+        }
+        for pem in self.root_certificates {
+            handler = handler.add_root_certificate(Certificate::from_pem(&pem)?);
+        }
+        if let Some(timeout) = self.pool_idle_timeout {
+            handler = handler.pool_idle_timeout(timeout);
+        }
+        if let Some(max) = self.pool_max_idle_per_host {
+            handler = handler.pool_max_idle_per_host(max);
+        }
+
+        Ok(Client {
+            config,
+            handler: handler.build()?,
+            retry: self.retry,
+            rate_limiter: self.rate_limiter,
+            middleware: self.middleware,
+            transport: self.transport,
+            cache: self.cache,
+            trace_content: self.trace_content,
+            metrics: self.metrics,
+            options: Options::default(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builder_base_url() {
+        let client = Client::builder()
+            .base_url("http://localhost:8080/v1")
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            client.config.url.join("chat/completions").unwrap().as_str(),
+            "http://localhost:8080/v1/chat/completions"
+        );
+    }
+
+    #[test]
+    fn test_builder_errors() {
+        assert!(matches!(
+            Client::builder().base_url("not a url").build(),
+            Err(Error::UrlError(_))
+        ));
+        assert!(matches!(
+            Client::builder().header("X-Team", "line\nbreak").build(),
+            Err(Error::HeaderValueError(_))
+        ));
+        assert!(matches!(
+            Client::builder().proxy("http://[::1").build(),
+            Err(Error::Reqwest(_))
+        ));
     }
 }
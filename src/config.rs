@@ -3,6 +3,8 @@
 use reqwest::header::HeaderMap;
 use url::Url;
 
+use crate::azure::AzureConfig;
+
 const DEFAULT_URL: &str = "https://api.openai.com/v1/";
 
 /// The configuration needed to establish connection with OpenAI's API.
@@ -16,6 +18,9 @@ pub struct Config {
     pub headers: HeaderMap,
 
     pub organization: String,
+
+    /// Set when talking to Azure OpenAI, rather than OpenAI itself.
+    pub azure: Option<AzureConfig>,
 }
 
 impl Default for Config {
@@ -25,6 +30,7 @@ impl Default for Config {
             url: Url::parse(DEFAULT_URL).unwrap(),
             headers: HeaderMap::new(),
             organization: String::new(),
+            azure: None,
         }
     }
 }
@@ -1,11 +1,55 @@
 //! A composite error type for errors that can occur while interacting with OpenAI.
 
+use std::{fmt, time::Duration};
+
+use reqwest::{header::HeaderMap, StatusCode};
+
 /// A set of errors that can occur during interaction with OpenAI.
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
+    /// An error reported without an unsuccessful status code, like in the middle of a stream.
     #[error("{}, {}", .0.error.r#type, .0.error.message)]
     APIError(RequestError),
 
+    /// Too many requests, or the quota of the organization has been exceeded.
+    #[error("Rate limited. {response}")]
+    RateLimited {
+        /// How long OpenAI asked to wait before trying again, up to a minute.
+        retry_after: Option<Duration>,
+        response: Box<ErrorResponse>,
+    },
+
+    /// The API key is missing, invalid or revoked.
+    #[error("Authentication failed. {0}")]
+    Authentication(Box<ErrorResponse>),
+
+    /// The API key isn't allowed to access the resource, or the region isn't supported.
+    #[error("Permission denied. {0}")]
+    PermissionDenied(Box<ErrorResponse>),
+
+    /// The resource, like a model or a file, doesn't exist.
+    #[error("Not found. {0}")]
+    NotFound(Box<ErrorResponse>),
+
+    /// The prompt, together with the requested completion, doesn't fit in the context of the model.
+    #[error("Context length exceeded. {0}")]
+    ContextLengthExceeded(Box<ErrorResponse>),
+
+    /// Any other rejected request.
+    #[error("Invalid request. {response}")]
+    InvalidRequest {
+        /// The parameter that caused the error, if OpenAI named one.
+        param: Option<String>,
+        response: Box<ErrorResponse>,
+    },
+
+    /// OpenAI, or something in between, failed to handle the request.
+    #[error("Server error. {response}")]
+    ServerError {
+        status: u16,
+        response: Box<ErrorResponse>,
+    },
+
     #[error(transparent)]
     Reqwest(#[from] reqwest::Error),
 
@@ -18,6 +62,45 @@ pub enum Error {
     #[error("{0}")]
     UrlError(#[from] url::ParseError),
 
+    #[error("{0}")]
+    HeaderNameError(#[from] reqwest::header::InvalidHeaderName),
+
+    #[error("{0}")]
+    HeaderValueError(#[from] reqwest::header::InvalidHeaderValue),
+
+    /// An error raised by a [`Middleware`](crate::middleware::Middleware).
+    #[error("{0}")]
+    Middleware(Box<dyn std::error::Error + Send + Sync>),
+
+    /// An error raised by a custom [`Transport`](crate::transport::Transport), like failing to connect.
+    #[error("{0}")]
+    Transport(Box<dyn std::error::Error + Send + Sync>),
+
+    #[error("No Azure deployment configured for {0} requests without a model.")]
+    MissingDeployment(String),
+
+    /// The model kept calling tools past the iterations allowed by [`run_with_tools`](crate::chat::run_with_tools).
+    #[error("No reply without tool calls after {0} iterations.")]
+    ToolIterationsExceeded(usize),
+
+    /// An image or audio file, larger than OpenAI accepts.
+    #[error("Media of {size} bytes exceeds the limit of {max} bytes.")]
+    MediaTooLarge { size: u64, max: u64 },
+
+    #[error("Unsupported media. {0}")]
+    UnsupportedMedia(String),
+
+    /// The model refused to reply in the requested format, with the given explanation.
+    #[error("The model refused to reply. {0}")]
+    Refusal(String),
+
+    /// The reply of the model doesn't deserialize into the requested type.
+    #[error("Invalid structured output. {source}")]
+    InvalidStructuredOutput {
+        content: String,
+        source: serde_json::Error,
+    },
+
     #[error("{0}")]
     SerdeError(#[from] serde_json::Error),
 
@@ -49,18 +132,197 @@ pub enum Error {
     ChatMessageBuilderError(#[from] crate::types::ChatMessageBuilderError),
 }
 
+impl Error {
+    /// Classifies an unsuccessful response by its status code and, where needed, its error code.
+    pub(crate) fn from_response(status: u16, headers: &HeaderMap, body: String) -> Self {
+        let response = Box::new(ErrorResponse {
+            status,
+            request_id: headers
+                .get("x-request-id")
+                .and_then(|v| v.to_str().ok())
+                .map(String::from),
+            error: serde_json::from_str::<RequestError>(&body)
+                .ok()
+                .map(|e| e.error),
+            body,
+        });
+
+        let code = response.error.as_ref().and_then(|e| e.code.as_str());
+        match status {
+            401 => Self::Authentication(response),
+            403 => Self::PermissionDenied(response),
+            404 => Self::NotFound(response),
+            429 => Self::RateLimited {
+                retry_after: crate::retry::requested_delay(
+                    StatusCode::TOO_MANY_REQUESTS,
+                    headers,
+                    crate::retry::DEFAULT_MAX_DELAY,
+                ),
+                response,
+            },
+            _ if code == Some("context_length_exceeded") => Self::ContextLengthExceeded(response),
+            500.. => Self::ServerError { status, response },
+            _ => Self::InvalidRequest {
+                param: response
+                    .error
+                    .as_ref()
+                    .and_then(|e| e.param.as_str())
+                    .map(String::from),
+                response,
+            },
+        }
+    }
+
+    /// The unsuccessful response OpenAI returned, if the error originates from one.
+    pub fn response(&self) -> Option<&ErrorResponse> {
+        match self {
+            Self::RateLimited { response, .. }
+            | Self::InvalidRequest { response, .. }
+            | Self::ServerError { response, .. }
+            | Self::Authentication(response)
+            | Self::PermissionDenied(response)
+            | Self::NotFound(response)
+            | Self::ContextLengthExceeded(response) => Some(response),
+            _ => None,
+        }
+    }
+
+    /// The HTTP status code of the unsuccessful response, if any.
+    pub fn status(&self) -> Option<u16> {
+        self.response().map(|r| r.status)
+    }
+
+    /// The `x-request-id` of the unsuccessful response, useful when reaching out to OpenAI's support.
+    pub fn request_id(&self) -> Option<&str> {
+        self.response().and_then(|r| r.request_id.as_deref())
+    }
+}
+
+/// An unsuccessful response from OpenAI.
+#[derive(Clone, Debug)]
+pub struct ErrorResponse {
+    /// The HTTP status code.
+    pub status: u16,
+
+    /// The value of the `x-request-id` header.
+    pub request_id: Option<String>,
+
+    /// The raw body, which isn't necessarily JSON, like with errors from proxies.
+    pub body: String,
+
+    /// The parsed body, if it's in OpenAI's error format.
+    pub error: Option<ErrorMessage>,
+}
+
+impl fmt::Display for ErrorResponse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.error {
+            Some(error) => write!(f, "{} ({})", error.message, self.status),
+            None => write!(f, "{} ({})", self.body.trim(), self.status),
+        }
+    }
+}
+
 /// Possible Errors returned by responses from OpenAI.
 #[derive(Clone, Debug, serde::Deserialize)]
 pub struct RequestError {
     pub error: ErrorMessage,
 }
 
+#[serde_with::serde_as]
 #[derive(Clone, Debug, std::default::Default, serde::Deserialize)]
 pub struct ErrorMessage {
     pub message: String,
+
+    // Azure returns null for errors like content filtering.
+    #[serde_as(as = "serde_with::DefaultOnNull")]
     pub r#type: String,
 
     // those are most frequently returned as null from OpenAI, even in the occurence of an error.
     pub param: serde_json::Value,
     pub code: serde_json::Value,
+
+    /// Details returned by Azure OpenAI.
+    #[serde(default)]
+    pub innererror: Option<Box<InnerError>>,
+}
+
+/// Details about errors returned by Azure OpenAI, like the results of content filtering.
+#[derive(Clone, Debug, std::default::Default, serde::Deserialize)]
+#[serde(default)]
+pub struct InnerError {
+    pub code: Option<String>,
+    pub content_filter_result: Option<crate::azure::ContentFilterResults>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers() -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert("x-request-id", "req-123".parse().unwrap());
+        headers.insert("retry-after", "3".parse().unwrap());
+        headers
+    }
+
+    fn body(code: &str, param: &str) -> String {
+        format!(
+            r#"{{"error": {{"message": "Something went wrong.", "type": "invalid_request_error", "param": {param}, "code": {code}}}}}"#
+        )
+    }
+
+    #[test]
+    fn test_error_classification() {
+        let err = Error::from_response(429, &headers(), body("null", "null"));
+        assert!(matches!(
+            err,
+            Error::RateLimited { retry_after: Some(d), .. } if d == Duration::from_secs(3)
+        ));
+        assert_eq!(err.request_id(), Some("req-123"));
+        assert_eq!(err.status(), Some(429));
+
+        assert!(matches!(
+            Error::from_response(401, &headers(), body("\"invalid_api_key\"", "null")),
+            Error::Authentication(_)
+        ));
+        assert!(matches!(
+            Error::from_response(404, &headers(), body("\"model_not_found\"", "null")),
+            Error::NotFound(_)
+        ));
+        assert!(matches!(
+            Error::from_response(
+                400,
+                &headers(),
+                body("\"context_length_exceeded\"", "\"messages\"")
+            ),
+            Error::ContextLengthExceeded(_)
+        ));
+        assert!(matches!(
+            Error::from_response(400, &headers(), body("null", "\"temperature\"")),
+            Error::InvalidRequest { param: Some(p), .. } if p == "temperature"
+        ));
+    }
+
+    #[test]
+    fn test_error_non_json_body() {
+        let err = Error::from_response(
+            502,
+            &HeaderMap::new(),
+            "<html>Bad Gateway</html>".to_string(),
+        );
+
+        match &err {
+            Error::ServerError { status, response } => {
+                assert_eq!(*status, 502);
+                assert_eq!(response.body, "<html>Bad Gateway</html>");
+                assert!(response.error.is_none());
+            }
+            _ => panic!("unexpected error: {err:?}"),
+        }
+        assert_eq!(
+            err.to_string(),
+            "Server error. <html>Bad Gateway</html> (502)"
+        );
+    }
 }
@@ -2,9 +2,27 @@
 #![deny(missing_debug_implementations, rust_2018_idioms)]
 
 pub mod api_resources;
+pub mod azure;
+#[cfg(feature = "blocking")]
+pub mod blocking;
+pub mod cache;
 pub mod client;
 mod config;
 pub mod error;
+pub mod meta;
+pub mod metrics;
+pub mod middleware;
+pub mod multipart;
+pub mod options;
+pub mod pool;
+pub mod rate_limit;
+pub mod retry;
+mod sse;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+pub mod tools;
+mod trace;
+pub mod transport;
 pub mod types;
 mod utils;
 
@@ -14,10 +32,13 @@ pub use api_resources::{
 };
 
 #[doc(inline)]
-pub use client::Client;
+pub use client::{Client, ClientBuilder};
 
 #[doc(inline)]
 pub use error::Error;
 
+#[doc(inline)]
+pub use options::RequestOptions;
+
 /// Result returned from each interaction with the OpenAI API.
 pub type Result<T> = std::result::Result<T, Error>;
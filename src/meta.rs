@@ -0,0 +1,259 @@
+//! Metadata OpenAI returns alongside each response, like the request id and the state of the rate limits.
+//!
+//! Metadata is opt-in: wrapping any call to an endpoint in [`with_meta`] returns the metadata
+//! of the response the endpoint received, next to its usual value.
+//! It's captured through a task-local, so requests sent from tasks spawned inside the call aren't seen,
+//! and responses answered from the [`Cache`](crate::cache::Cache) only have their body, flagged as [`cached`](ResponseMeta::cached).
+//!
+//! ## Usage
+//! ```no_run
+//! use fieri::{
+//!     chat::chat,
+//!     meta::with_meta,
+//!     types::{ChatMessageBuilder, ChatParamBuilder},
+//!     Client,
+//! };
+//!
+//! #[tokio::main]
+//! async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//!     let client = Client::new();
+//!
+//!     let message = ChatMessageBuilder::new("user", "Hello!").build()?;
+//!     let param = ChatParamBuilder::new("gpt-3.5-turbo", vec![message]).build()?;
+//!
+//!     let resp = with_meta(chat(&client, &param)).await?;
+//!     println!("{:?} took {:?}", resp.meta.request_id, resp.meta.processing_time);
+//!     println!("{:?} requests left", resp.meta.rate_limit.remaining_requests);
+//!
+//!     Ok(())
+//! }
+//! ```
+
+use std::{
+    future::Future,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use reqwest::header::HeaderMap;
+use serde_json::Value;
+
+use crate::{retry::parse_duration, Result};
+
+tokio::task_local! {
+    static META: Arc<Mutex<Option<ResponseMeta>>>;
+}
+
+/// A value, together with the metadata of the response it came from.
+#[derive(Clone, Debug)]
+pub struct WithMeta<T> {
+    pub value: T,
+    pub meta: ResponseMeta,
+}
+
+/// The metadata of a response.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ResponseMeta {
+    /// The `x-request-id` header, useful when reaching out to OpenAI's support.
+    pub request_id: Option<String>,
+
+    /// How long OpenAI took to process the request, from the `openai-processing-ms` header.
+    pub processing_time: Option<Duration>,
+
+    /// The model that served the request, from the `openai-model` header.
+    pub model: Option<String>,
+
+    /// The organization the request was billed to, from the `openai-organization` header.
+    pub organization: Option<String>,
+
+    pub rate_limit: RateLimitStatus,
+
+    /// The JSON body of the response, including the fields the returned types don't declare.
+    ///
+    /// Not captured for streams.
+    pub raw: Option<Value>,
+
+    /// Whether the response was answered from the [`Cache`](crate::cache::Cache), rather than by OpenAI.
+    ///
+    /// The headers of cached responses aren't stored, so only [`raw`](Self::raw) is set alongside.
+    pub cached: bool,
+}
+
+/// The state of the rate limits of the organization, from the `x-ratelimit-*` headers.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RateLimitStatus {
+    /// The maximum number of requests allowed before exhausting the rate limit.
+    pub limit_requests: Option<u64>,
+
+    /// The maximum number of tokens allowed before exhausting the rate limit.
+    pub limit_tokens: Option<u64>,
+
+    pub remaining_requests: Option<u64>,
+    pub remaining_tokens: Option<u64>,
+
+    /// The time until the request limit resets to its initial state.
+    pub reset_requests: Option<Duration>,
+
+    /// The time until the token limit resets to its initial state.
+    pub reset_tokens: Option<Duration>,
+}
+
+impl ResponseMeta {
+    pub(crate) fn from_headers(headers: &HeaderMap) -> Self {
+        let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok());
+        let number = |name: &str| header(name).and_then(|v| v.parse().ok());
+
+        Self {
+            request_id: header("x-request-id").map(String::from),
+            processing_time: number("openai-processing-ms").map(Duration::from_millis),
+            model: header("openai-model").map(String::from),
+            organization: header("openai-organization").map(String::from),
+            rate_limit: RateLimitStatus {
+                limit_requests: number("x-ratelimit-limit-requests"),
+                limit_tokens: number("x-ratelimit-limit-tokens"),
+                remaining_requests: number("x-ratelimit-remaining-requests"),
+                remaining_tokens: number("x-ratelimit-remaining-tokens"),
+                reset_requests: header("x-ratelimit-reset-requests").and_then(parse_duration),
+                reset_tokens: header("x-ratelimit-reset-tokens").and_then(parse_duration),
+            },
+            raw: None,
+            cached: false,
+        }
+    }
+}
+
+/// Runs the given call to an endpoint, capturing the metadata of the response it received.
+///
+/// If the call makes multiple requests, like when retrying, the metadata of the last response is returned.
+///
+/// Only requests sent from the task running the call are captured: the metadata of requests sent
+/// from tasks it spawns, like with `tokio::spawn`, is lost. Responses answered from the [`Cache`](crate::cache::Cache)
+/// have no headers, so their metadata only holds the body, with [`cached`](ResponseMeta::cached) set.
+pub async fn with_meta<F, T>(call: F) -> Result<WithMeta<T>>
+where
+    F: Future<Output = Result<T>>,
+{
+    let slot = Arc::new(Mutex::new(None));
+    let value = META.scope(slot.clone(), call).await?;
+
+    let meta = slot.lock().unwrap().take().unwrap_or_default();
+    Ok(WithMeta { value, meta })
+}
+
+/// Records the metadata of a response, if the current call is wrapped in [`with_meta`].
+pub(crate) fn record(headers: &HeaderMap) {
+    let _ = META.try_with(|slot| {
+        *slot.lock().unwrap() = Some(ResponseMeta::from_headers(headers));
+    });
+}
+
+/// Records that the response was answered from the cache, if the current call is wrapped in [`with_meta`].
+///
+/// Replaces the metadata of any previous response, which doesn't describe this one.
+pub(crate) fn record_cached() {
+    let _ = META.try_with(|slot| {
+        *slot.lock().unwrap() = Some(ResponseMeta {
+            cached: true,
+            ..ResponseMeta::default()
+        });
+    });
+}
+
+/// Records the JSON body of a response, if the current call is wrapped in [`with_meta`].
+///
+/// Bodies answered from the cache have no headers, so only their body is recorded.
+pub(crate) fn record_body(body: &[u8]) {
+    let _ = META.try_with(|slot| {
+        slot.lock()
+            .unwrap()
+            .get_or_insert_with(Default::default)
+            .raw = serde_json::from_slice(body).ok();
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers() -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in [
+            ("x-request-id", "req-123"),
+            ("openai-processing-ms", "294"),
+            ("openai-model", "gpt-3.5-turbo-0613"),
+            ("x-ratelimit-limit-requests", "3500"),
+            ("x-ratelimit-limit-tokens", "90000"),
+            ("x-ratelimit-remaining-requests", "3499"),
+            ("x-ratelimit-remaining-tokens", "89981"),
+            ("x-ratelimit-reset-requests", "17ms"),
+            ("x-ratelimit-reset-tokens", "12ms"),
+        ] {
+            headers.insert(name, value.parse().unwrap());
+        }
+
+        headers
+    }
+
+    #[test]
+    fn test_meta_from_headers() {
+        let meta = ResponseMeta::from_headers(&headers());
+
+        assert_eq!(meta.request_id.as_deref(), Some("req-123"));
+        assert_eq!(meta.processing_time, Some(Duration::from_millis(294)));
+        assert_eq!(meta.model.as_deref(), Some("gpt-3.5-turbo-0613"));
+        assert_eq!(meta.organization, None);
+        assert_eq!(meta.rate_limit.remaining_tokens, Some(89981));
+        assert_eq!(
+            meta.rate_limit.reset_requests,
+            Some(Duration::from_millis(17))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_with_meta() {
+        let resp = with_meta(async {
+            record(&headers());
+            Ok("value")
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(resp.value, "value");
+        assert_eq!(resp.meta.request_id.as_deref(), Some("req-123"));
+        assert_eq!(resp.meta.raw, None);
+
+        // Outside of `with_meta`, nothing is recorded.
+        record(&headers());
+    }
+
+    #[tokio::test]
+    async fn test_with_meta_raw() {
+        let resp = with_meta(async {
+            record(&headers());
+            record_body(br#"{"id":"chatcmpl-123","service_tier":"default"}"#);
+            Ok(())
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(resp.meta.request_id.as_deref(), Some("req-123"));
+        assert_eq!(resp.meta.raw.unwrap()["service_tier"], "default");
+        assert!(!resp.meta.cached);
+    }
+
+    #[tokio::test]
+    async fn test_with_meta_cached() {
+        let resp = with_meta(async {
+            record(&headers());
+            record_cached();
+            record_body(br#"{"id":"chatcmpl-123"}"#);
+            Ok(())
+        })
+        .await
+        .unwrap();
+
+        assert!(resp.meta.cached);
+        assert_eq!(resp.meta.request_id, None);
+        assert_eq!(resp.meta.raw.unwrap()["id"], "chatcmpl-123");
+    }
+}
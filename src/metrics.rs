@@ -0,0 +1,446 @@
+//! Metrics of each request: its latency, outcome, retries, token usage and estimated spend.
+//!
+//! Once a request completes, or the stream it returned ends, the [`Client`](crate::Client) reports
+//! its [`RequestMetrics`] to the configured [`MetricsSink`].
+//!
+//! With the `metrics` feature, [`MetricsRecorder`] forwards them to the [`metrics`](https://docs.rs/metrics) facade,
+//! and from there to any exporter, like Prometheus:
+//!
+//! | Metric | Type | Labels |
+//! |--------|------|--------|
+//! | `fieri_requests_total` | counter | `endpoint`, `model`, `status` |
+//! | `fieri_errors_total` | counter | `endpoint`, `model`, `kind` |
+//! | `fieri_request_duration_seconds` | histogram | `endpoint`, `model` |
+//! | `fieri_time_to_first_token_seconds` | histogram | `endpoint`, `model` |
+//! | `fieri_retries_total` | counter | `endpoint`, `model` |
+//! | `fieri_cache_hits_total` | counter | `endpoint`, `model` |
+//! | `fieri_tokens_total` | counter | `endpoint`, `model`, `type` (`prompt` or `completion`) |
+//! | `fieri_estimated_cost_dollars` | gauge, only ever incremented | `endpoint`, `model` |
+//!
+//! Labels added with [`MetricsRecorder::label`], like the name of the service, are attached to every metric.
+//!
+//! ## Usage
+//! ```no_run
+//! use fieri::{metrics::RequestMetrics, Client};
+//!
+//! let client = Client::new().metrics(|metrics: &RequestMetrics| {
+//!     println!("{} took {:?}", metrics.endpoint, metrics.latency);
+//! });
+//! ```
+
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use crate::Error;
+
+/// Resources whose endpoints take an ID as their second segment, like `files/{id}`.
+const RESOURCES_WITH_IDS: &[&str] = &["files", "fine-tunes", "models"];
+
+/// Receives the metrics of each request.
+pub trait MetricsSink: Send + Sync + 'static {
+    fn record(&self, metrics: &RequestMetrics);
+}
+
+impl<F> MetricsSink for F
+where
+    F: Fn(&RequestMetrics) + Send + Sync + 'static,
+{
+    fn record(&self, metrics: &RequestMetrics) {
+        self(metrics)
+    }
+}
+
+/// The metrics of a single request.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RequestMetrics {
+    /// The endpoint, with IDs replaced by `{id}` to keep its cardinality low, like `files/{id}`.
+    pub endpoint: String,
+    pub method: &'static str,
+
+    /// The model requested, if any.
+    pub model: Option<String>,
+
+    /// The status of the last response, unless no response was received.
+    pub status: Option<u16>,
+
+    /// The kind of error the request failed with, if it did, like `rate_limited` or `connection`.
+    pub error: Option<&'static str>,
+
+    /// The time from the first attempt until the response, or the whole stream, has been read.
+    pub latency: Duration,
+
+    /// For streams, the time until the first event.
+    pub time_to_first_token: Option<Duration>,
+    pub retries: u32,
+
+    /// Whether the response was answered by the [`Cache`](crate::cache::Cache), without reaching OpenAI.
+    pub cached: bool,
+    pub prompt_tokens: Option<u64>,
+    pub completion_tokens: Option<u64>,
+}
+
+impl RequestMetrics {
+    pub(crate) fn new(method: &'static str, identifier: &str, model: Option<String>) -> Self {
+        Self {
+            endpoint: endpoint(identifier),
+            method,
+            model,
+            status: None,
+            error: None,
+            latency: Duration::ZERO,
+            time_to_first_token: None,
+            retries: 0,
+            cached: false,
+            prompt_tokens: None,
+            completion_tokens: None,
+        }
+    }
+}
+
+/// The price of a model, in dollars per million tokens.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Price {
+    pub prompt: f64,
+    pub completion: f64,
+}
+
+impl Price {
+    pub fn new(prompt: f64, completion: f64) -> Self {
+        Self { prompt, completion }
+    }
+}
+
+/// The prices of models, to estimate the spend of each request.
+///
+/// Models are matched by their longest known prefix, so `gpt-4-0613` is priced as `gpt-4`.
+/// The default prices are OpenAI's list prices at the time of writing; override them with [`model`](Self::model).
+#[derive(Clone, Debug, PartialEq)]
+pub struct Pricing {
+    models: HashMap<String, Price>,
+}
+
+impl Default for Pricing {
+    fn default() -> Self {
+        let prices = [
+            ("gpt-4o-mini", Price::new(0.15, 0.6)),
+            ("gpt-4o", Price::new(2.5, 10.0)),
+            ("gpt-4-turbo", Price::new(10.0, 30.0)),
+            ("gpt-4-32k", Price::new(60.0, 120.0)),
+            ("gpt-4", Price::new(30.0, 60.0)),
+            ("gpt-3.5-turbo-instruct", Price::new(1.5, 2.0)),
+            ("gpt-3.5-turbo", Price::new(0.5, 1.5)),
+            ("davinci-002", Price::new(2.0, 2.0)),
+            ("babbage-002", Price::new(0.4, 0.4)),
+            ("text-embedding-3-small", Price::new(0.02, 0.0)),
+            ("text-embedding-3-large", Price::new(0.13, 0.0)),
+            ("text-embedding-ada-002", Price::new(0.1, 0.0)),
+        ];
+
+        Self {
+            models: prices
+                .into_iter()
+                .map(|(model, price)| (model.to_string(), price))
+                .collect(),
+        }
+    }
+}
+
+impl Pricing {
+    /// Creates a pricing without any model.
+    pub fn new() -> Self {
+        Self {
+            models: HashMap::new(),
+        }
+    }
+
+    /// Use the given price for the given model, and the models it's a prefix of.
+    pub fn model(mut self, model: impl Into<String>, price: Price) -> Self {
+        self.models.insert(model.into(), price);
+
+        self
+    }
+
+    pub fn price(&self, model: &str) -> Option<Price> {
+        self.models
+            .iter()
+            .filter(|(prefix, _)| model.starts_with(prefix.as_str()))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map(|(_, price)| *price)
+    }
+
+    /// The estimated spend of the given request, in dollars, if its model & usage are known.
+    ///
+    /// Requests answered by the cache cost nothing.
+    pub fn cost(&self, metrics: &RequestMetrics) -> Option<f64> {
+        if metrics.cached {
+            return Some(0.0);
+        }
+
+        let price = self.price(metrics.model.as_deref()?)?;
+        let prompt = metrics.prompt_tokens? as f64;
+        let completion = metrics.completion_tokens.unwrap_or(0) as f64;
+
+        Some((prompt * price.prompt + completion * price.completion) / 1_000_000.0)
+    }
+}
+
+/// The configured [`MetricsSink`].
+#[derive(Clone)]
+pub(crate) struct Sink(pub Arc<dyn MetricsSink>);
+
+impl fmt::Debug for Sink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Sink")
+    }
+}
+
+/// The metrics of a request in progress, reported to the sink once dropped.
+pub(crate) struct Report {
+    sink: Arc<dyn MetricsSink>,
+    start: Instant,
+    pub metrics: RequestMetrics,
+}
+
+impl Report {
+    pub fn new(sink: &Sink, metrics: RequestMetrics) -> Arc<Mutex<Self>> {
+        Arc::new(Mutex::new(Self {
+            sink: sink.0.clone(),
+            start: Instant::now(),
+            metrics,
+        }))
+    }
+}
+
+impl fmt::Debug for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Report")
+            .field("start", &self.start)
+            .field("metrics", &self.metrics)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Drop for Report {
+    fn drop(&mut self) {
+        self.metrics.latency = self.start.elapsed();
+        self.sink.record(&self.metrics);
+    }
+}
+
+/// The endpoint of the given identifier, without IDs.
+fn endpoint(identifier: &str) -> String {
+    let mut segments: Vec<&str> = identifier.split('/').collect();
+    if segments.len() > 1 && RESOURCES_WITH_IDS.contains(&segments[0]) {
+        segments[1] = "{id}";
+    }
+
+    segments.join("/")
+}
+
+/// The kind of the given error, as reported in [`RequestMetrics::error`].
+pub(crate) fn kind(err: &Error) -> &'static str {
+    match err {
+        Error::APIError(_) => "api",
+        Error::RateLimited { .. } => "rate_limited",
+        Error::Authentication(_) => "authentication",
+        Error::PermissionDenied(_) => "permission_denied",
+        Error::NotFound(_) => "not_found",
+        Error::ContextLengthExceeded(_) => "context_length_exceeded",
+        Error::InvalidRequest { .. } => "invalid_request",
+        Error::ServerError { .. } => "server",
+        Error::Reqwest(err) if err.is_timeout() => "timeout",
+        Error::Reqwest(err) if err.is_decode() || err.is_body() => "body",
+        Error::Reqwest(_) => "connection",
+        Error::Middleware(_) => "middleware",
+        Error::Transport(_) => "connection",
+        Error::SerdeError(_) => "decode",
+        _ => "other",
+    }
+}
+
+/// A [`MetricsSink`] forwarding metrics to the [`metrics`](https://docs.rs/metrics) facade.
+///
+/// Only available with the `metrics` feature.
+#[cfg(feature = "metrics")]
+#[derive(Clone, Debug, Default)]
+pub struct MetricsRecorder {
+    labels: Vec<(String, String)>,
+    pricing: Pricing,
+}
+
+#[cfg(feature = "metrics")]
+impl MetricsRecorder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A label attached to every metric, like `("service", "billing")`.
+    pub fn label(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.labels.push((name.into(), value.into()));
+
+        self
+    }
+
+    /// The prices used to estimate the spend of each request, [`Pricing::default`] if not set.
+    pub fn pricing(mut self, pricing: Pricing) -> Self {
+        self.pricing = pricing;
+
+        self
+    }
+}
+
+#[cfg(feature = "metrics")]
+impl MetricsSink for MetricsRecorder {
+    fn record(&self, metrics: &RequestMetrics) {
+        use ::metrics::{counter, gauge, histogram, Label};
+
+        let labels = |extra: &[(&'static str, String)]| -> Vec<Label> {
+            self.labels
+                .iter()
+                .map(|(k, v)| Label::new(k.clone(), v.clone()))
+                .chain([
+                    Label::new("endpoint", metrics.endpoint.clone()),
+                    Label::new("model", metrics.model.clone().unwrap_or_default()),
+                ])
+                .chain(extra.iter().map(|(k, v)| Label::new(*k, v.clone())))
+                .collect()
+        };
+
+        let status = metrics
+            .status
+            .map(|s| s.to_string())
+            .unwrap_or_else(|| "none".to_string());
+        counter!("fieri_requests_total", labels(&[("status", status)])).increment(1);
+        if let Some(kind) = metrics.error {
+            counter!("fieri_errors_total", labels(&[("kind", kind.to_string())])).increment(1);
+        }
+
+        histogram!("fieri_request_duration_seconds", labels(&[])).record(metrics.latency);
+        if let Some(ttft) = metrics.time_to_first_token {
+            histogram!("fieri_time_to_first_token_seconds", labels(&[])).record(ttft);
+        }
+        if metrics.retries > 0 {
+            counter!("fieri_retries_total", labels(&[])).increment(metrics.retries.into());
+        }
+
+        if metrics.cached {
+            counter!("fieri_cache_hits_total", labels(&[])).increment(1);
+            return;
+        }
+
+        for (r#type, tokens) in [
+            ("prompt", metrics.prompt_tokens),
+            ("completion", metrics.completion_tokens),
+        ] {
+            if let Some(tokens) = tokens {
+                counter!(
+                    "fieri_tokens_total",
+                    labels(&[("type", r#type.to_string())])
+                )
+                .increment(tokens);
+            }
+        }
+        if let Some(cost) = self.pricing.cost(metrics) {
+            gauge!("fieri_estimated_cost_dollars", labels(&[])).increment(cost);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        chat::{chat, chat_stream},
+        file::retrieve,
+        testing::{Method, MockResponse, MockTransport},
+        types::{ChatMessageBuilder, ChatParam, ChatParamBuilder},
+        Client,
+    };
+    use futures::StreamExt;
+
+    #[derive(Clone, Default)]
+    struct Collector(Arc<Mutex<Vec<RequestMetrics>>>);
+
+    impl MetricsSink for Collector {
+        fn record(&self, metrics: &RequestMetrics) {
+            self.0.lock().unwrap().push(metrics.clone());
+        }
+    }
+
+    fn param() -> ChatParam {
+        let message = ChatMessageBuilder::new("user", "Hello!").build().unwrap();
+
+        ChatParamBuilder::new("gpt-4-0613", vec![message])
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_endpoint() {
+        assert_eq!(endpoint("chat/completions"), "chat/completions");
+        assert_eq!(endpoint("files/file-abc123"), "files/{id}");
+        assert_eq!(
+            endpoint("fine-tunes/ft-abc123/events"),
+            "fine-tunes/{id}/events"
+        );
+    }
+
+    #[test]
+    fn test_pricing() {
+        let pricing = Pricing::default().model("gpt-4-0613", Price::new(1.0, 2.0));
+        let mut metrics =
+            RequestMetrics::new("POST", "chat/completions", Some("gpt-4-0314".into()));
+        metrics.prompt_tokens = Some(1_000);
+        metrics.completion_tokens = Some(500);
+
+        assert_eq!(pricing.cost(&metrics), Some(0.06));
+
+        metrics.model = Some("gpt-4-0613".into());
+        assert_eq!(pricing.cost(&metrics), Some(0.002));
+
+        metrics.model = Some("unknown".into());
+        assert_eq!(pricing.cost(&metrics), None);
+    }
+
+    #[tokio::test]
+    async fn test_client_metrics() {
+        let mock = MockTransport::with_fixtures();
+        mock.once(
+            Method::GET,
+            "files/*",
+            MockResponse::error(404, "No such File object.", "invalid_request_error", None),
+        );
+        let collector = Collector::default();
+        let client: Client = mock.client().metrics(collector.clone());
+
+        chat(&client, &param()).await.unwrap();
+        retrieve(&client, "file-abc123").await.unwrap_err();
+
+        let mut stream = chat_stream(&client, &param()).await.unwrap();
+        stream.next().await.unwrap().unwrap();
+        // Streams are only reported once they're done with.
+        assert_eq!(collector.0.lock().unwrap().len(), 2);
+        drop(stream);
+
+        let metrics = collector.0.lock().unwrap().clone();
+        assert_eq!(metrics.len(), 3);
+
+        assert_eq!(metrics[0].endpoint, "chat/completions");
+        assert_eq!(metrics[0].model.as_deref(), Some("gpt-4-0613"));
+        assert_eq!(metrics[0].status, Some(200));
+        assert_eq!(metrics[0].prompt_tokens, Some(9));
+        assert_eq!(metrics[0].completion_tokens, Some(9));
+        assert_eq!(metrics[0].error, None);
+
+        assert_eq!(metrics[1].endpoint, "files/{id}");
+        assert_eq!(metrics[1].status, Some(404));
+        assert_eq!(metrics[1].error, Some("not_found"));
+
+        assert!(metrics[2].time_to_first_token.is_some());
+        assert!(metrics[2].latency >= metrics[2].time_to_first_token.unwrap());
+    }
+}
@@ -0,0 +1,173 @@
+//! Hooks around each request sent by a [`Client`](crate::Client), like logging, auditing or rewriting requests.
+//!
+//! A [`Middleware`] receives each request right before it's sent, together with the rest of the chain.
+//! It may inspect or modify the request, pass it on with [`Next::run`], and inspect or replace the response,
+//! or skip the rest of the chain altogether.
+//!
+//! Middleware runs in the order it was added to the client, for every attempt of a request, after it's been authorized.
+//! Streamed responses are handed over as soon as their headers arrive.
+//!
+//! ## Usage
+//! ```no_run
+//! use fieri::{
+//!     middleware::{Middleware, Next, Request, Response},
+//!     Client, Result,
+//! };
+//! use futures::future::BoxFuture;
+//!
+//! struct Audit;
+//!
+//! impl Middleware for Audit {
+//!     fn handle<'a>(&'a self, request: Request, next: Next<'a>) -> BoxFuture<'a, Result<Response>> {
+//!         Box::pin(async move {
+//!             println!("-> {} {}", request.method(), request.url());
+//!             let resp = next.run(request).await?;
+//!             println!("<- {}", resp.status());
+//!
+//!             Ok(resp)
+//!         })
+//!     }
+//! }
+//!
+//! let client = Client::new().middleware(Audit);
+//! ```
+
+use std::{fmt, sync::Arc};
+
+use futures::future::BoxFuture;
+
+use crate::{transport::Transport, Result};
+
+pub use crate::transport::{Body, Request, Response};
+
+/// A hook around each request sent by a [`Client`](crate::Client).
+pub trait Middleware: Send + Sync + 'static {
+    /// Handles the request, usually by passing it on to `next`.
+    fn handle<'a>(&'a self, request: Request, next: Next<'a>) -> BoxFuture<'a, Result<Response>>;
+}
+
+/// The rest of the chain, ending with sending the request.
+#[derive(Clone, Copy)]
+pub struct Next<'a> {
+    transport: &'a dyn Transport,
+    middleware: &'a [Arc<dyn Middleware>],
+}
+
+impl<'a> Next<'a> {
+    pub(crate) fn new(transport: &'a dyn Transport, middleware: &'a [Arc<dyn Middleware>]) -> Self {
+        Self {
+            transport,
+            middleware,
+        }
+    }
+
+    /// Passes the request on to the next middleware, or sends it if there's none left.
+    pub fn run(self, request: Request) -> BoxFuture<'a, Result<Response>> {
+        match self.middleware.split_first() {
+            Some((first, rest)) => first.handle(request, Next::new(self.transport, rest)),
+            None => self.transport.send(request),
+        }
+    }
+}
+
+impl fmt::Debug for Next<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Next")
+            .field("middleware", &self.middleware.len())
+            .finish()
+    }
+}
+
+/// The middleware of a client, in order.
+#[derive(Clone, Default)]
+pub(crate) struct Stack(pub Vec<Arc<dyn Middleware>>);
+
+impl fmt::Debug for Stack {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Stack({} middleware)", self.0.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use http::Method;
+    use url::Url;
+
+    use super::*;
+    use crate::Error;
+
+    struct Header(&'static str);
+
+    impl Middleware for Header {
+        fn handle<'a>(
+            &'a self,
+            mut request: Request,
+            next: Next<'a>,
+        ) -> BoxFuture<'a, Result<Response>> {
+            request
+                .headers_mut()
+                .append("x-trace", self.0.parse().unwrap());
+
+            next.run(request)
+        }
+    }
+
+    /// Answers with the `x-trace` headers it received, instead of sending the request.
+    struct Echo;
+
+    impl Middleware for Echo {
+        fn handle<'a>(&'a self, request: Request, _: Next<'a>) -> BoxFuture<'a, Result<Response>> {
+            let trace: Vec<_> = request
+                .headers()
+                .get_all("x-trace")
+                .iter()
+                .map(|v| v.to_str().unwrap().to_string())
+                .collect();
+
+            Box::pin(async move { Ok(Response::new(trace.join(","))) })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_middleware_order() {
+        let handler = reqwest::Client::new();
+        let middleware: Vec<Arc<dyn Middleware>> = vec![
+            Arc::new(Header("first")),
+            Arc::new(Header("second")),
+            Arc::new(Echo),
+        ];
+
+        let request = Request::new(
+            Method::GET,
+            Url::parse("http://localhost/v1/models").unwrap(),
+        );
+        let resp = Next::new(&handler, &middleware).run(request).await.unwrap();
+
+        assert_eq!(resp.text().await.unwrap(), "first,second");
+    }
+
+    #[tokio::test]
+    async fn test_middleware_short_circuit() {
+        struct Deny;
+
+        impl Middleware for Deny {
+            fn handle<'a>(&'a self, _: Request, _: Next<'a>) -> BoxFuture<'a, Result<Response>> {
+                Box::pin(async { Err(Error::Middleware("denied".into())) })
+            }
+        }
+
+        let handler = reqwest::Client::new();
+        let middleware: Vec<Arc<dyn Middleware>> = vec![Arc::new(Deny), Arc::new(Echo)];
+
+        let request = Request::new(
+            Method::GET,
+            Url::parse("http://localhost/v1/models").unwrap(),
+        );
+        let err = Next::new(&handler, &middleware)
+            .run(request)
+            .await
+            .unwrap_err();
+
+        assert!(matches!(err, Error::Middleware(e) if e.to_string() == "denied"));
+    }
+}
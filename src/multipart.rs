@@ -0,0 +1,155 @@
+//! Multipart forms, for endpoints uploading files like [`Upload File`](crate::file::upload).
+//!
+//! Forms are encoded up front, so that retries, [`Middleware`](crate::middleware::Middleware)
+//! and [`Transport`](crate::transport::Transport)s see the whole body.
+
+use std::borrow::Cow;
+
+use bytes::Bytes;
+
+/// A `multipart/form-data` body.
+#[derive(Clone, Debug)]
+pub struct Form {
+    boundary: String,
+    fields: Vec<(String, Part)>,
+}
+
+/// A field of a [`Form`], either text or a file.
+#[derive(Clone, Debug)]
+pub struct Part {
+    value: Bytes,
+    file_name: Option<String>,
+    mime: Option<String>,
+}
+
+impl Form {
+    pub fn new() -> Self {
+        Self {
+            boundary: format!(
+                "{:016x}-{:016x}-{:016x}-{:016x}",
+                fastrand::u64(..),
+                fastrand::u64(..),
+                fastrand::u64(..),
+                fastrand::u64(..)
+            ),
+            fields: Vec::new(),
+        }
+    }
+
+    pub fn boundary(&self) -> &str {
+        &self.boundary
+    }
+
+    /// Adds a text field.
+    pub fn text(self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.part(name, Part::text(value))
+    }
+
+    pub fn part(mut self, name: impl Into<String>, part: Part) -> Self {
+        self.fields.push((name.into(), part));
+
+        self
+    }
+
+    /// The `Content-Type` header of the encoded form.
+    pub fn content_type(&self) -> String {
+        format!("multipart/form-data; boundary={}", self.boundary)
+    }
+
+    /// Encodes the form into the body of a request.
+    pub fn encode(&self) -> Bytes {
+        let mut body = Vec::new();
+
+        for (name, part) in &self.fields {
+            body.extend_from_slice(format!("--{}\r\n", self.boundary).as_bytes());
+            body.extend_from_slice(
+                format!("Content-Disposition: form-data; name=\"{}\"", escape(name)).as_bytes(),
+            );
+            if let Some(file_name) = &part.file_name {
+                body.extend_from_slice(format!("; filename=\"{}\"", escape(file_name)).as_bytes());
+            }
+            if let Some(mime) = &part.mime {
+                body.extend_from_slice(format!("\r\nContent-Type: {}", escape(mime)).as_bytes());
+            }
+            body.extend_from_slice(b"\r\n\r\n");
+            body.extend_from_slice(&part.value);
+            body.extend_from_slice(b"\r\n");
+        }
+        body.extend_from_slice(format!("--{}--\r\n", self.boundary).as_bytes());
+
+        body.into()
+    }
+}
+
+impl Default for Form {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Part {
+    pub fn text(value: impl Into<String>) -> Self {
+        Self::bytes(value.into())
+    }
+
+    pub fn bytes(value: impl Into<Bytes>) -> Self {
+        Self {
+            value: value.into(),
+            file_name: None,
+            mime: None,
+        }
+    }
+
+    /// The name of the uploaded file.
+    pub fn file_name(mut self, file_name: impl Into<Cow<'static, str>>) -> Self {
+        self.file_name = Some(file_name.into().into_owned());
+
+        self
+    }
+
+    /// The `Content-Type` of the part, like `image/png`.
+    pub fn mime(mut self, mime: impl Into<String>) -> Self {
+        self.mime = Some(mime.into());
+
+        self
+    }
+}
+
+/// Escapes quotes & line breaks of names & mime types, the way browsers do.
+fn escape(s: &str) -> String {
+    s.replace('"', "%22")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode() {
+        let form = Form::new()
+            .part(
+                "file",
+                Part::bytes(&b"{}"[..])
+                    .file_name("data\".jsonl")
+                    .mime("application/jsonl\r\nX-Injected: 1"),
+            )
+            .text("purpose", "fine-tune");
+        let boundary = form.boundary().to_string();
+
+        assert_eq!(
+            String::from_utf8(form.encode().to_vec()).unwrap(),
+            format!(
+                "--{boundary}\r\n\
+                 Content-Disposition: form-data; name=\"file\"; filename=\"data%22.jsonl\"\r\n\
+                 Content-Type: application/jsonl%0D%0AX-Injected: 1\r\n\r\n\
+                 {{}}\r\n\
+                 --{boundary}\r\n\
+                 Content-Disposition: form-data; name=\"purpose\"\r\n\r\n\
+                 fine-tune\r\n\
+                 --{boundary}--\r\n"
+            )
+        );
+    }
+}
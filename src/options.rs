@@ -0,0 +1,278 @@
+//! Options of a single request, merged over the defaults of the [`Client`](crate::Client).
+//!
+//! Endpoints have a `_with` variant taking [`RequestOptions`], like [`chat_with`](crate::chat::chat_with),
+//! while [`Client::with_options`](crate::Client::with_options) applies them to any endpoint, including the deprecated ones.
+//!
+//! ## Usage
+//! ```no_run
+//! use std::time::Duration;
+//! use fieri::{
+//!     chat::chat_with,
+//!     types::{ChatMessageBuilder, ChatParamBuilder},
+//!     Client, RequestOptions,
+//! };
+//!
+//! #[tokio::main]
+//! async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//!     let client = Client::new();
+//!
+//!     let message = ChatMessageBuilder::new("user", "Hello!").build()?;
+//!     let param = ChatParamBuilder::new("gpt-3.5-turbo", vec![message]).build()?;
+//!     let options = RequestOptions::new()
+//!         .timeout(Duration::from_secs(300))
+//!         .header("OpenAI-Beta", "assistants=v2")
+//!         .idempotency_key("order-1234")
+//!         .body_field("store", true);
+//!
+//!     let resp = chat_with(&client, &param, options).await?;
+//!     println!("{:#?}", resp);
+//!
+//!     Ok(())
+//! }
+//! ```
+
+use std::time::Duration;
+
+use http::header::{HeaderMap, HeaderName, HeaderValue};
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use crate::{multipart::Form, transport::Request, Result};
+
+/// The header carrying the [idempotency key](RequestOptions::idempotency_key).
+const IDEMPOTENCY_KEY: &str = "Idempotency-Key";
+
+/// Options of a single request.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RequestOptions {
+    timeout: Option<Duration>,
+    headers: Vec<(String, String)>,
+    idempotency_key: Option<String>,
+    query: Vec<(String, String)>,
+    body: Map<String, Value>,
+
+    /// The first body field that failed to serialize, reported when the options are merged.
+    body_error: Option<String>,
+}
+
+impl RequestOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The timeout of the request, in place of the one of the client.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+
+        self
+    }
+
+    /// A header sent with the request, replacing any header of the client with the same name.
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+
+        self
+    }
+
+    /// Sent as the `Idempotency-Key` header, the same for every retry of the request.
+    pub fn idempotency_key(mut self, key: impl Into<String>) -> Self {
+        self.idempotency_key = Some(key.into());
+
+        self
+    }
+
+    /// A parameter added to the query string of the request.
+    pub fn query(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.query.push((name.into(), value.into()));
+
+        self
+    }
+
+    /// A field added to the body of the request, replacing the field of the parameters with the same name.
+    ///
+    /// Useful for parameters that fieri's types don't model yet. Multipart uploads receive the field as text,
+    /// while requests without a body, like `GET`s, ignore it.
+    /// Values that fail to serialize to JSON make the request fail, before it's sent.
+    pub fn body_field(mut self, name: impl Into<String>, value: impl Serialize) -> Self {
+        let name = name.into();
+        match serde_json::to_value(value) {
+            Ok(value) => {
+                self.body.insert(name, value);
+            }
+            Err(err) => {
+                self.body_error
+                    .get_or_insert_with(|| format!("Invalid body field `{name}`: {err}"));
+            }
+        }
+
+        self
+    }
+}
+
+/// [`RequestOptions`], validated & merged into the ones of a client.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Options {
+    timeout: Option<Duration>,
+    headers: HeaderMap,
+    query: Vec<(String, String)>,
+    body: Map<String, Value>,
+}
+
+impl Options {
+    /// Merges the given options over these ones.
+    pub fn merge(&self, options: RequestOptions) -> Result<Self> {
+        if let Some(err) = options.body_error {
+            return Err(<serde_json::Error as serde::ser::Error>::custom(err).into());
+        }
+
+        let mut merged = self.clone();
+
+        merged.timeout = options.timeout.or(self.timeout);
+        for (name, value) in options.headers {
+            merged
+                .headers
+                .insert(HeaderName::try_from(name)?, HeaderValue::try_from(value)?);
+        }
+        if let Some(key) = options.idempotency_key {
+            merged
+                .headers
+                .insert(IDEMPOTENCY_KEY, HeaderValue::try_from(key)?);
+        }
+        merged.query.extend(options.query);
+        merged.body.extend(options.body);
+
+        Ok(merged)
+    }
+
+    /// Applies the options to the given request, after the defaults of the client.
+    pub fn apply(&self, mut request: Request) -> Request {
+        if let Some(timeout) = self.timeout {
+            *request.timeout_mut() = Some(timeout);
+        }
+        if !self.query.is_empty() {
+            request
+                .url_mut()
+                .query_pairs_mut()
+                .extend_pairs(&self.query);
+        }
+        for (name, value) in &self.headers {
+            request.headers_mut().insert(name, value.clone());
+        }
+
+        request
+    }
+
+    /// The given body, extended with the extra fields, unless there are none.
+    pub fn extend<X: Serialize>(&self, body: Option<&X>) -> Result<Option<Value>> {
+        if self.body.is_empty() {
+            return Ok(None);
+        }
+
+        let mut body = match body {
+            Some(body) => serde_json::to_value(body)?,
+            None => Value::Object(Map::new()),
+        };
+        if let Value::Object(fields) = &mut body {
+            fields.extend(self.body.clone());
+        }
+
+        Ok(Some(body))
+    }
+
+    /// The given form, extended with the extra fields.
+    pub fn extend_form(&self, mut form: Form) -> Form {
+        for (name, value) in &self.body {
+            let value = match value {
+                Value::String(value) => value.clone(),
+                value => value.to_string(),
+            };
+            form = form.text(name.clone(), value);
+        }
+
+        form
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        chat::chat_with,
+        model::list_with,
+        retry::RetryPolicy,
+        testing::{Method, MockResponse, MockTransport},
+        types::{ChatMessageBuilder, ChatParamBuilder},
+        Client,
+    };
+
+    #[tokio::test(start_paused = true)]
+    async fn test_options() {
+        let mock = MockTransport::with_fixtures();
+        mock.once(
+            Method::POST,
+            "chat/completions",
+            MockResponse::error(500, "The server had an error.", "server_error", None),
+        );
+        let client = Client::builder()
+            .api_key("sk-test")
+            .header("OpenAI-Beta", "assistants=v1")
+            .transport(mock.clone())
+            .retry(RetryPolicy::new())
+            .build()
+            .unwrap();
+
+        let message = ChatMessageBuilder::new("user", "Hello!").build().unwrap();
+        let param = ChatParamBuilder::new("gpt-3.5-turbo", vec![message])
+            .temperature(0.5)
+            .build()
+            .unwrap();
+        let options = RequestOptions::new()
+            .timeout(Duration::from_secs(300))
+            .header("OpenAI-Beta", "assistants=v2")
+            .idempotency_key("order-1234")
+            .query("api-version", "beta")
+            .body_field("store", true)
+            .body_field("temperature", 0.0);
+        chat_with(&client, &param, options).await.unwrap();
+
+        let requests = mock.requests();
+        assert_eq!(requests.len(), 2);
+        for request in &requests {
+            assert_eq!(request.headers["openai-beta"], "assistants=v2");
+            assert_eq!(request.headers.get_all("openai-beta").iter().count(), 1);
+            assert_eq!(request.headers["idempotency-key"], "order-1234");
+            assert_eq!(request.query.as_deref(), Some("api-version=beta"));
+            assert_eq!(request.timeout, Some(Duration::from_secs(300)));
+
+            let body = request.json().unwrap();
+            assert_eq!(body["store"], true);
+            assert_eq!(body["temperature"], 0.0);
+            assert_eq!(body["messages"][0]["content"], "Hello!");
+        }
+
+        // Options only apply to the call they're given to.
+        list_with(&client, RequestOptions::new().body_field("ignored", true))
+            .await
+            .unwrap();
+        let request = mock.assert_requested(Method::GET, "models");
+        assert_eq!(request.headers["openai-beta"], "assistants=v1");
+        assert!(request.timeout.is_none());
+        assert!(request.body.is_none());
+
+        assert!(client
+            .with_options(RequestOptions::new().header("X-Bad", "line\nbreak"))
+            .is_err());
+
+        // Maps with non-string keys can't be serialized to JSON.
+        let bad = std::collections::HashMap::from([((1, 2), true)]);
+        let err = chat_with(
+            &client,
+            &param,
+            RequestOptions::new().body_field("bad", bad),
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(err, crate::Error::SerdeError(e) if e.to_string().contains("`bad`")));
+        assert_eq!(mock.requests().len(), 3);
+    }
+}
@@ -0,0 +1,524 @@
+//! Spreading requests over several clients, like different API keys, organizations or Azure regions.
+//!
+//! A [`ClientPool`] picks a member for each call, by [`Strategy`]. When the member fails with
+//! `401 Unauthorized`, `429 Too Many Requests`, a `5xx`, or fails to connect or times out, it's ejected for a while,
+//! and the call is transparently retried on another member. Ejections double with each consecutive failure,
+//! and `429`s last at least as long as OpenAI asked to wait.
+//!
+//! When every member is ejected, calls go to the one that recovers first, rather than failing outright.
+//!
+//! Clones of a pool share the stats of the members they have in common; members added afterwards only belong to one of them.
+//!
+//! ## Usage
+//! ```no_run
+//! use fieri::{
+//!     azure::AzureConfig,
+//!     chat::chat,
+//!     pool::{ClientPool, Strategy},
+//!     types::{ChatMessageBuilder, ChatParamBuilder},
+//!     Client,
+//! };
+//!
+//! #[tokio::main]
+//! async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//!     let pool = ClientPool::new()
+//!         .strategy(Strategy::LeastLoaded)
+//!         .member("openai", Client::new().api_key("sk-..."), 3)
+//!         .member(
+//!             "azure-westeurope",
+//!             Client::builder()
+//!                 .azure(
+//!                     AzureConfig::new("https://westeurope.openai.azure.com", "2024-02-01")
+//!                         .deployment("gpt-35-turbo", "chat"),
+//!                 )
+//!                 .api_key("...")
+//!                 .build()?,
+//!             1,
+//!         );
+//!
+//!     let message = ChatMessageBuilder::new("user", "Hello!").build()?;
+//!     let param = &ChatParamBuilder::new("gpt-35-turbo", vec![message]).build()?;
+//!     let resp = pool.call(|client| async move { chat(&client, param).await }).await?;
+//!     println!("{:#?}", resp);
+//!
+//!     for member in pool.stats() {
+//!         println!("{}: {} requests, healthy: {}", member.name, member.requests, member.healthy);
+//!     }
+//!
+//!     Ok(())
+//! }
+//! ```
+
+use std::{
+    future::Future,
+    sync::{Arc, Mutex, MutexGuard},
+    time::Duration,
+};
+
+use tokio::time::Instant;
+
+use crate::{metrics::RequestMetrics, Client, Error, Result};
+
+/// How long a member is ejected after its first failure.
+const EJECTION: Duration = Duration::from_secs(30);
+
+/// The most an ejection doubles, with consecutive failures.
+const MAX_EJECTION_FACTOR: u32 = 32;
+
+/// The longest ejection, so that its end always fits in an [`Instant`].
+const MAX_EJECTION: Duration = Duration::from_secs(365 * 24 * 60 * 60);
+
+/// How a [`ClientPool`] picks the member serving a call.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Strategy {
+    /// In turns, each member taking a share of calls proportional to its weight.
+    #[default]
+    WeightedRoundRobin,
+
+    /// The member with the fewest calls in flight, relative to its weight.
+    LeastLoaded,
+}
+
+/// The health & usage of a member of a [`ClientPool`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MemberStats {
+    pub name: String,
+    pub weight: u32,
+
+    /// Whether the member currently receives calls.
+    pub healthy: bool,
+
+    /// How much longer the member is ejected for, if it is.
+    pub ejected_for: Option<Duration>,
+    pub in_flight: usize,
+
+    /// Calls the member served, including failed ones.
+    pub requests: u64,
+    pub failures: u64,
+    pub consecutive_failures: u32,
+
+    /// The status of the last failure, unless it had no response.
+    pub last_failure_status: Option<u16>,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+}
+
+/// Clients sharing the calls made through the pool, with failover between them.
+#[derive(Clone, Debug, Default)]
+pub struct ClientPool {
+    strategy: Strategy,
+    ejection: Option<Duration>,
+    members: Vec<Member>,
+}
+
+#[derive(Clone, Debug)]
+struct Member {
+    name: String,
+    weight: u32,
+    client: Client,
+
+    /// Shared with the metrics sink of `client`, and with clones of the pool.
+    state: Arc<Mutex<MemberState>>,
+}
+
+#[derive(Debug, Default)]
+struct MemberState {
+    /// The running weight of the smooth weighted round-robin.
+    current_weight: i64,
+    ejected_until: Option<Instant>,
+    in_flight: usize,
+    requests: u64,
+    failures: u64,
+    consecutive_failures: u32,
+    last_failure_status: Option<u16>,
+    prompt_tokens: u64,
+    completion_tokens: u64,
+}
+
+impl ClientPool {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// How members are picked, [`Strategy::WeightedRoundRobin`] by default.
+    pub fn strategy(mut self, strategy: Strategy) -> Self {
+        self.strategy = strategy;
+
+        self
+    }
+
+    /// How long a member is ejected after its first failure, 30 seconds by default.
+    ///
+    /// Ejections double with each consecutive failure, up to a year.
+    pub fn ejection(mut self, ejection: Duration) -> Self {
+        self.ejection = Some(ejection);
+
+        self
+    }
+
+    /// Add a member, receiving a share of calls proportional to the given weight.
+    ///
+    /// A weight of 0 is treated as 1.
+    pub fn member(mut self, name: impl Into<String>, client: Client, weight: u32) -> Self {
+        let state = Arc::new(Mutex::new(MemberState::default()));
+        let observed = state.clone();
+        let client = client.observe(move |metrics: &RequestMetrics| {
+            if metrics.cached {
+                return;
+            }
+
+            let mut member = observed.lock().unwrap();
+            member.prompt_tokens += metrics.prompt_tokens.unwrap_or(0);
+            member.completion_tokens += metrics.completion_tokens.unwrap_or(0);
+        });
+
+        self.members.push(Member {
+            name: name.into(),
+            weight: weight.max(1),
+            client,
+            state,
+        });
+
+        self
+    }
+
+    /// Makes the given call with a member of the pool, retrying it on another member if it fails
+    /// in a way that ejects the first one.
+    ///
+    /// Each member is tried at most once per call. Other errors are returned right away.
+    ///
+    /// # Panics
+    /// If the pool has no members.
+    pub async fn call<T, F, Fut>(&self, call: F) -> Result<T>
+    where
+        F: Fn(Client) -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        assert!(!self.members.is_empty(), "The pool has no members.");
+
+        let mut tried = vec![false; self.members.len()];
+        loop {
+            let index = self.pick(&tried);
+            tried[index] = true;
+
+            let result = {
+                let _in_flight = InFlight::new(self, index);
+                call(self.members[index].client.clone()).await
+            };
+
+            match &result {
+                Err(err) if ejects(err) => {
+                    self.eject(index, err);
+                    if tried.iter().all(|t| *t) {
+                        return result;
+                    }
+                }
+                _ => {
+                    self.members[index]
+                        .state
+                        .lock()
+                        .unwrap()
+                        .consecutive_failures = 0;
+
+                    return result;
+                }
+            }
+        }
+    }
+
+    /// The health & usage of each member, in the order they were added.
+    pub fn stats(&self) -> Vec<MemberStats> {
+        let now = Instant::now();
+
+        self.members
+            .iter()
+            .map(|member| {
+                let state = member.state.lock().unwrap();
+                let ejected_for = state
+                    .ejected_until
+                    .filter(|until| *until > now)
+                    .map(|until| until - now);
+
+                MemberStats {
+                    name: member.name.clone(),
+                    weight: member.weight,
+                    healthy: ejected_for.is_none(),
+                    ejected_for,
+                    in_flight: state.in_flight,
+                    requests: state.requests,
+                    failures: state.failures,
+                    consecutive_failures: state.consecutive_failures,
+                    last_failure_status: state.last_failure_status,
+                    prompt_tokens: state.prompt_tokens,
+                    completion_tokens: state.completion_tokens,
+                }
+            })
+            .collect()
+    }
+
+    /// Picks the member serving the next call, out of the ones not tried yet.
+    fn pick(&self, tried: &[bool]) -> usize {
+        let now = Instant::now();
+        let mut state = self.lock();
+
+        let untried: Vec<usize> = (0..self.members.len()).filter(|i| !tried[*i]).collect();
+        let healthy: Vec<usize> = untried
+            .iter()
+            .copied()
+            .filter(|i| state[*i].ejected_until.map_or(true, |until| until <= now))
+            .collect();
+
+        // Without a healthy member, the one recovering first is the best bet.
+        if healthy.is_empty() {
+            return untried
+                .into_iter()
+                .min_by_key(|i| state[*i].ejected_until)
+                .expect("Err picking a member.");
+        }
+
+        match self.strategy {
+            Strategy::WeightedRoundRobin => {
+                let total: i64 = healthy.iter().map(|i| self.members[*i].weight as i64).sum();
+                for i in &healthy {
+                    state[*i].current_weight += self.members[*i].weight as i64;
+                }
+
+                let index = *healthy
+                    .iter()
+                    .max_by_key(|i| (state[**i].current_weight, std::cmp::Reverse(**i)))
+                    .expect("Err picking a member.");
+                state[index].current_weight -= total;
+
+                index
+            }
+            Strategy::LeastLoaded => *healthy
+                .iter()
+                .min_by(|a, b| {
+                    let load = |i: usize| state[i].in_flight as f64 / self.members[i].weight as f64;
+                    load(**a).total_cmp(&load(**b))
+                })
+                .expect("Err picking a member."),
+        }
+    }
+
+    fn eject(&self, index: usize, err: &Error) {
+        let mut member = self.members[index].state.lock().unwrap();
+
+        member.failures += 1;
+        member.consecutive_failures += 1;
+        member.last_failure_status = err.status();
+
+        let factor = 2_u32
+            .saturating_pow(member.consecutive_failures - 1)
+            .min(MAX_EJECTION_FACTOR);
+        let mut ejection = self.ejection.unwrap_or(EJECTION).saturating_mul(factor);
+        if let Error::RateLimited {
+            retry_after: Some(retry_after),
+            ..
+        } = err
+        {
+            ejection = ejection.max(*retry_after);
+        }
+
+        member.ejected_until = Some(Instant::now() + ejection.min(MAX_EJECTION));
+    }
+
+    /// Locks the state of every member, always in the same order.
+    fn lock(&self) -> Vec<MutexGuard<'_, MemberState>> {
+        self.members
+            .iter()
+            .map(|m| m.state.lock().unwrap())
+            .collect()
+    }
+}
+
+/// Whether the given error ejects the member it came from.
+///
+/// Requests that never got a response only eject when the member couldn't be reached,
+/// not when, say, the body failed to decode.
+fn ejects(err: &Error) -> bool {
+    match err {
+        Error::Reqwest(err) => err.is_connect() || err.is_timeout(),
+        Error::Transport(_) => true,
+        _ => matches!(err.status(), Some(401 | 429 | 500..=599)),
+    }
+}
+
+/// Counts a call in flight, until dropped.
+struct InFlight<'a> {
+    pool: &'a ClientPool,
+    index: usize,
+}
+
+impl<'a> InFlight<'a> {
+    fn new(pool: &'a ClientPool, index: usize) -> Self {
+        let mut state = pool.members[index].state.lock().unwrap();
+        state.in_flight += 1;
+        state.requests += 1;
+
+        Self { pool, index }
+    }
+}
+
+impl Drop for InFlight<'_> {
+    fn drop(&mut self) {
+        self.pool.members[self.index]
+            .state
+            .lock()
+            .unwrap()
+            .in_flight -= 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        chat::chat,
+        model::list,
+        testing::{Method, MockResponse, MockTransport},
+        types::{ChatMessageBuilder, ChatParamBuilder},
+    };
+
+    #[tokio::test]
+    async fn test_weighted_round_robin() {
+        let (a, b) = (
+            MockTransport::with_fixtures(),
+            MockTransport::with_fixtures(),
+        );
+        let pool = ClientPool::new()
+            .member("a", a.client(), 2)
+            .member("b", b.client(), 1);
+
+        for _ in 0..6 {
+            pool.call(|client| async move { list(&client).await })
+                .await
+                .unwrap();
+        }
+
+        assert_eq!(a.requests().len(), 4);
+        assert_eq!(b.requests().len(), 2);
+        assert_eq!(pool.stats()[0].requests, 4);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_failover() {
+        let (a, b) = (
+            MockTransport::with_fixtures(),
+            MockTransport::with_fixtures(),
+        );
+        a.on(
+            Method::POST,
+            "chat/completions",
+            MockResponse::error(503, "The server is overloaded.", "server_error", None),
+        );
+        let pool = ClientPool::new()
+            .ejection(Duration::from_secs(10))
+            .member("a", a.client(), 1)
+            .member("b", b.client(), 1);
+
+        let message = ChatMessageBuilder::new("user", "Hello!").build().unwrap();
+        let param = &ChatParamBuilder::new("gpt-3.5-turbo", vec![message])
+            .build()
+            .unwrap();
+        pool.call(|client| async move { chat(&client, param).await })
+            .await
+            .unwrap();
+
+        let stats = pool.stats();
+        assert!(!stats[0].healthy);
+        assert_eq!(stats[0].ejected_for, Some(Duration::from_secs(10)));
+        assert_eq!(stats[0].last_failure_status, Some(503));
+        assert_eq!(stats[1].requests, 1);
+        assert_eq!(stats[1].prompt_tokens, 9);
+
+        // Ejected members get no calls, until they recover.
+        pool.call(|client| async move { list(&client).await })
+            .await
+            .unwrap();
+        assert_eq!(a.requests().len(), 1);
+
+        tokio::time::advance(Duration::from_secs(10)).await;
+        assert!(pool.stats()[0].healthy);
+
+        // Errors that don't eject are returned as is.
+        for mock in [&a, &b] {
+            mock.once(
+                Method::GET,
+                "files",
+                MockResponse::error(400, "Invalid request.", "invalid_request_error", None),
+            );
+        }
+        let err = pool
+            .call(|client| async move { crate::file::list(&client).await })
+            .await
+            .unwrap_err();
+        assert_eq!(err.status(), Some(400));
+        assert!(pool.stats().iter().all(|m| m.healthy));
+        assert_eq!(a.requests().len() + b.requests().len(), 4);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_long_ejection() {
+        let mock = MockTransport::with_fixtures();
+        mock.on(
+            Method::GET,
+            "models",
+            MockResponse::error(503, "The server is overloaded.", "server_error", None),
+        );
+        let pool = ClientPool::new()
+            .ejection(Duration::MAX)
+            .member("a", mock.client(), 1);
+
+        for _ in 0..2 {
+            tokio::time::advance(MAX_EJECTION).await;
+            assert!(pool
+                .call(|client| async move { list(&client).await })
+                .await
+                .is_err());
+        }
+
+        assert_eq!(pool.stats()[0].ejected_for, Some(MAX_EJECTION));
+    }
+
+    #[tokio::test]
+    async fn test_clones_share_members() {
+        let (a, b) = (
+            MockTransport::with_fixtures(),
+            MockTransport::with_fixtures(),
+        );
+        let pool = ClientPool::new().member("a", a.client(), 1);
+        let clone = pool.clone().member("b", b.client(), 1);
+        let pool = pool.member("c", MockTransport::with_fixtures().client(), 1);
+
+        let message = ChatMessageBuilder::new("user", "Hello!").build().unwrap();
+        let param = &ChatParamBuilder::new("gpt-3.5-turbo", vec![message])
+            .build()
+            .unwrap();
+        for _ in 0..2 {
+            clone
+                .call(|client| async move { chat(&client, param).await })
+                .await
+                .unwrap();
+        }
+
+        let stats = pool.stats();
+        assert_eq!((stats[0].requests, stats[0].prompt_tokens), (1, 9));
+        assert_eq!((stats[1].name.as_str(), stats[1].requests), ("c", 0));
+        assert_eq!(clone.stats()[1].prompt_tokens, 9);
+    }
+
+    #[tokio::test]
+    async fn test_ejects() {
+        let unreachable = Client::builder()
+            .api_key("sk-test")
+            .base_url("http://127.0.0.1:1/v1/")
+            .build()
+            .unwrap();
+        let err = list(&unreachable).await.unwrap_err();
+        assert!(ejects(&err));
+
+        let err = serde_json::from_str::<serde_json::Value>("{").unwrap_err();
+        assert!(!ejects(&err.into()));
+    }
+}
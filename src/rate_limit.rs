@@ -0,0 +1,428 @@
+//! Client-side rate limiting, to stay within the requests & tokens per minute allowed to an organization.
+//!
+//! Each model gets a pair of token buckets, refilled continuously over a minute.
+//! Before a request is sent, its cost in tokens is estimated from its input & `max_tokens`,
+//! and the request waits until both buckets can afford it. Once the response arrives,
+//! the estimate is corrected with the actual [`TokenUsage`](crate::types::TokenUsage).
+//! Requests that never reached OpenAI, like the ones failing to connect, are refunded entirely.
+//!
+//! Clones of a [`RateLimiter`], including the ones held by clones of a [`Client`](crate::Client), share the same buckets.
+//!
+//! ## Usage
+//! ```no_run
+//! use fieri::{
+//!     rate_limit::{Limits, RateLimiter},
+//!     Client,
+//! };
+//!
+//! let client = Client::new().rate_limiter(
+//!     RateLimiter::new()
+//!         .requests_per_minute(3_500)
+//!         .tokens_per_minute(90_000)
+//!         .model("gpt-4", Limits::new().requests_per_minute(500).tokens_per_minute(10_000)),
+//! );
+//! ```
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use serde::Deserialize;
+use serde_json::Value;
+use tokio::time::Instant;
+
+use crate::{types::TokenUsage, Error};
+
+/// A rough average of characters per token, for English text.
+const CHARS_PER_TOKEN: usize = 4;
+
+/// The tokens counted for each image or audio part of a message, whatever its size.
+///
+/// That's what a high detail, 1024x1024 image costs; the base64 data of media says nothing about its tokens.
+const MEDIA_TOKENS: usize = 765;
+
+/// The types of message parts counted as [`MEDIA_TOKENS`].
+const MEDIA_PARTS: &[&str] = &["image_url", "input_audio"];
+
+/// The fields of a request holding its input.
+const INPUT_FIELDS: &[&str] = &["messages", "prompt", "input", "instruction"];
+
+/// The requests & tokens allowed per minute.
+///
+/// Unset limits are not enforced.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Limits {
+    pub requests_per_minute: Option<u32>,
+    pub tokens_per_minute: Option<u32>,
+}
+
+impl Limits {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn requests_per_minute(mut self, requests: u32) -> Self {
+        self.requests_per_minute = Some(requests);
+
+        self
+    }
+
+    pub fn tokens_per_minute(mut self, tokens: u32) -> Self {
+        self.tokens_per_minute = Some(tokens);
+
+        self
+    }
+}
+
+/// Delays requests that would exceed the configured [`Limits`].
+#[derive(Clone, Debug, Default)]
+pub struct RateLimiter {
+    /// The limits of models without limits of their own.
+    limits: Limits,
+
+    /// Limits specific to a model.
+    models: HashMap<String, Limits>,
+
+    /// The buckets of each model, shared between clones.
+    buckets: Arc<Mutex<HashMap<String, Buckets>>>,
+}
+
+impl RateLimiter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The requests per minute allowed to each model, unless overridden by [`model`](Self::model).
+    pub fn requests_per_minute(mut self, requests: u32) -> Self {
+        self.limits.requests_per_minute = Some(requests);
+
+        self
+    }
+
+    /// The tokens per minute allowed to each model, unless overridden by [`model`](Self::model).
+    pub fn tokens_per_minute(mut self, tokens: u32) -> Self {
+        self.limits.tokens_per_minute = Some(tokens);
+
+        self
+    }
+
+    /// Use the given limits for the given model.
+    pub fn model(mut self, model: impl Into<String>, limits: Limits) -> Self {
+        self.models.insert(model.into(), limits);
+
+        self
+    }
+
+    /// Waits until the given model can afford a request costing the given number of tokens, and reserves them.
+    pub(crate) async fn acquire(&self, model: &str, tokens: u32) -> Permit {
+        loop {
+            let wait = {
+                let mut buckets = self.buckets.lock().unwrap();
+                let limits = self.models.get(model).unwrap_or(&self.limits);
+                let buckets = buckets
+                    .entry(model.to_string())
+                    .or_insert_with(|| Buckets::new(limits));
+
+                let now = Instant::now();
+                let wait = buckets.wait(now, tokens);
+                if wait.is_zero() {
+                    return Permit {
+                        limiter: self.clone(),
+                        model: model.to_string(),
+                        tokens: buckets.take(tokens),
+                    };
+                }
+
+                wait
+            };
+
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
+/// Tokens reserved for a request, to be corrected once its usage is known.
+#[derive(Debug)]
+pub(crate) struct Permit {
+    limiter: RateLimiter,
+    model: String,
+
+    /// The tokens actually taken from the bucket.
+    tokens: f64,
+}
+
+impl Permit {
+    /// Corrects the reserved tokens with the usage reported in the response body, if any.
+    pub fn settle(self, body: &[u8]) {
+        #[derive(Deserialize)]
+        struct Usage {
+            usage: Option<TokenUsage>,
+        }
+
+        let Some(usage) = serde_json::from_slice::<Usage>(body)
+            .ok()
+            .and_then(|u| u.usage)
+        else {
+            return;
+        };
+
+        let mut buckets = self.limiter.buckets.lock().unwrap();
+        if let Some(bucket) = buckets.get_mut(&self.model).and_then(|b| b.tokens.as_mut()) {
+            bucket.adjust(self.tokens - usage.total_tokens as f64);
+        }
+    }
+
+    /// Gives back the request & tokens reserved, for a request that never reached OpenAI.
+    pub fn refund(self) {
+        let mut buckets = self.limiter.buckets.lock().unwrap();
+        let Some(buckets) = buckets.get_mut(&self.model) else {
+            return;
+        };
+
+        if let Some(bucket) = &mut buckets.requests {
+            bucket.adjust(1.0);
+        }
+        if let Some(bucket) = &mut buckets.tokens {
+            bucket.adjust(self.tokens);
+        }
+    }
+}
+
+/// Whether the given error means the request never reached OpenAI, so it cost nothing.
+pub(crate) fn unsent(err: &Error) -> bool {
+    match err {
+        Error::Reqwest(err) => err.is_connect() || err.is_builder(),
+        Error::Transport(_)
+        | Error::Middleware(_)
+        | Error::HeaderNameError(_)
+        | Error::HeaderValueError(_) => true,
+        _ => false,
+    }
+}
+
+#[derive(Debug)]
+struct Buckets {
+    requests: Option<Bucket>,
+    tokens: Option<Bucket>,
+}
+
+impl Buckets {
+    fn new(limits: &Limits) -> Self {
+        Self {
+            requests: limits.requests_per_minute.map(Bucket::new),
+            tokens: limits.tokens_per_minute.map(Bucket::new),
+        }
+    }
+
+    /// How long until both buckets can afford a request of the given cost.
+    fn wait(&mut self, now: Instant, tokens: u32) -> Duration {
+        let requests = self.requests.as_mut().map(|b| b.wait(now, 1.0));
+        let tokens = self.tokens.as_mut().map(|b| b.wait(now, tokens as f64));
+
+        requests.max(tokens).unwrap_or_default()
+    }
+
+    /// Takes a request of the given cost from the buckets, returning the tokens taken.
+    fn take(&mut self, tokens: u32) -> f64 {
+        if let Some(bucket) = &mut self.requests {
+            bucket.adjust(-1.0);
+        }
+
+        match &mut self.tokens {
+            Some(bucket) => {
+                let tokens = (tokens as f64).min(bucket.capacity);
+                bucket.adjust(-tokens);
+
+                tokens
+            }
+            None => 0.0,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Bucket {
+    capacity: f64,
+    available: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    fn new(per_minute: u32) -> Self {
+        Self {
+            capacity: per_minute as f64,
+            available: per_minute as f64,
+            updated: Instant::now(),
+        }
+    }
+
+    fn per_second(&self) -> f64 {
+        self.capacity / 60.0
+    }
+
+    /// Refills the bucket, and returns how long until it holds the given amount.
+    ///
+    /// Amounts larger than the bucket are capped, so that they wait for a full bucket instead of forever.
+    fn wait(&mut self, now: Instant, amount: f64) -> Duration {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.available = (self.available + elapsed * self.per_second()).min(self.capacity);
+        self.updated = now;
+
+        let missing = amount.min(self.capacity) - self.available;
+        if missing <= 0.0 || self.per_second() == 0.0 {
+            return Duration::ZERO;
+        }
+
+        Duration::from_secs_f64(missing / self.per_second())
+    }
+
+    /// Adds to, or with a negative amount takes from, the bucket.
+    ///
+    /// The bucket may go into debt, when a request used more tokens than estimated.
+    fn adjust(&mut self, amount: f64) {
+        self.available = (self.available + amount).min(self.capacity);
+    }
+}
+
+/// Estimates the tokens a request will use, from the length of its input and its `max_tokens`.
+///
+/// Images & audio count as a fixed number of tokens each, rather than by the length of their data.
+pub(crate) fn estimate(body: &Value) -> u32 {
+    fn chars(value: &Value) -> usize {
+        match value {
+            Value::String(s) => s.chars().count(),
+            Value::Array(values) => values.iter().map(chars).sum(),
+            Value::Object(map)
+                if map
+                    .get("type")
+                    .and_then(Value::as_str)
+                    .is_some_and(|t| MEDIA_PARTS.contains(&t)) =>
+            {
+                MEDIA_TOKENS * CHARS_PER_TOKEN
+            }
+            Value::Object(map) => map.values().map(chars).sum(),
+            _ => 0,
+        }
+    }
+
+    let input: usize = INPUT_FIELDS
+        .iter()
+        .filter_map(|field| body.get(field))
+        .map(chars)
+        .sum();
+
+    let completions = body.get("n").and_then(Value::as_u64).unwrap_or(1);
+    let output = body
+        .get("max_tokens")
+        .and_then(Value::as_u64)
+        .unwrap_or(0)
+        .saturating_mul(completions);
+
+    let total = (((input + CHARS_PER_TOKEN - 1) / CHARS_PER_TOKEN) as u64).saturating_add(output);
+    total.min(u32::MAX as u64) as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_estimate() {
+        let body = serde_json::json!({
+            "model": "gpt-3.5-turbo",
+            "messages": [{"role": "user", "content": "Hello there!"}],
+            "max_tokens": 10,
+            "n": 2,
+        });
+
+        // "user" + "Hello there!" is 16 characters, or 4 tokens.
+        assert_eq!(estimate(&body), 24);
+        assert_eq!(estimate(&serde_json::json!({"input": ["abcd", "efgh"]})), 2);
+
+        // Media counts the same, however large its data.
+        let image = |url: String| {
+            serde_json::json!({
+                "messages": [{"role": "user", "content": [
+                    {"type": "text", "text": "What's this?"},
+                    {"type": "image_url", "image_url": {"url": url}},
+                ]}],
+            })
+        };
+        let small = estimate(&image("data:image/png;base64,AAAA".to_string()));
+        let large = estimate(&image(format!(
+            "data:image/png;base64,{}",
+            "A".repeat(1 << 20)
+        )));
+        assert_eq!(small, large);
+        // "user", "text" & "What's this?" are 20 characters, or 5 tokens.
+        assert_eq!(small as usize, MEDIA_TOKENS + 5);
+    }
+
+    #[tokio::test]
+    async fn test_refund_unsent() {
+        let limiter = RateLimiter::new()
+            .requests_per_minute(10)
+            .tokens_per_minute(1_000);
+        let client = crate::Client::builder()
+            .api_key("sk-test")
+            .base_url("http://127.0.0.1:1/v1/")
+            .rate_limiter(limiter.clone())
+            .build()
+            .unwrap();
+
+        let body = serde_json::json!({"model": "gpt-4", "prompt": "Hello", "max_tokens": 500});
+        let err = client
+            .post::<_, Value>("completions", Some(&body))
+            .await
+            .unwrap_err();
+        assert!(unsent(&err));
+
+        let buckets = limiter.buckets.lock().unwrap();
+        let buckets = &buckets["gpt-4"];
+        assert_eq!(buckets.requests.as_ref().unwrap().available, 10.0);
+        assert!(buckets.tokens.as_ref().unwrap().available >= 1_000.0);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_requests_per_minute() {
+        let limiter = RateLimiter::new().requests_per_minute(60);
+        let start = Instant::now();
+
+        // The whole minute's worth is available immediately, after which requests trickle in once a second.
+        for _ in 0..60 {
+            limiter.acquire("gpt-4", 0).await;
+        }
+        assert_eq!(start.elapsed(), Duration::ZERO);
+
+        limiter.clone().acquire("gpt-4", 0).await;
+        assert!(start.elapsed() >= Duration::from_secs(1));
+
+        // Other models have their own buckets.
+        let start = Instant::now();
+        limiter.acquire("gpt-3.5-turbo", 0).await;
+        assert_eq!(start.elapsed(), Duration::ZERO);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_tokens_per_minute_settle() {
+        let limiter = RateLimiter::new()
+            .tokens_per_minute(1_000)
+            .model("gpt-4", Limits::new().tokens_per_minute(600));
+
+        // The request used far fewer tokens than estimated, so the rest is given back.
+        let permit = limiter.acquire("gpt-4", 600).await;
+        permit.settle(
+            br#"{"usage": {"prompt_tokens": 50, "completion_tokens": 10, "total_tokens": 60}}"#,
+        );
+
+        let start = Instant::now();
+        limiter.acquire("gpt-4", 500).await;
+        assert_eq!(start.elapsed(), Duration::ZERO);
+
+        // 40 tokens are left, refilled at 10 a second.
+        limiter.acquire("gpt-4", 100).await;
+        assert!(start.elapsed() >= Duration::from_secs(6));
+    }
+}
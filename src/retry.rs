@@ -0,0 +1,351 @@
+//! Automatically retrying requests that failed for transient reasons, like rate limits or server overload.
+//!
+//! ## Usage
+//! ```no_run
+//! use std::time::Duration;
+//! use fieri::{retry::RetryPolicy, Client};
+//!
+//! let client = Client::new().retry(
+//!     RetryPolicy::new()
+//!         .max_attempts(5)
+//!         .base_delay(Duration::from_secs(1)),
+//! );
+//! ```
+
+use std::time::{Duration, SystemTime};
+
+use reqwest::{header::HeaderMap, StatusCode};
+
+use crate::{transport::Response, Error, Result};
+
+/// The default upper bound of any delay, also applied to the delays reported in [`Error::RateLimited`].
+pub(crate) const DEFAULT_MAX_DELAY: Duration = Duration::from_secs(60);
+
+/// Describes when, and how long after, a failed request is attempted again.
+///
+/// Delays grow exponentially with each attempt, unless OpenAI tells us how long to wait
+/// through the `Retry-After` (in seconds or as an HTTP date), `retry-after-ms` or `x-ratelimit-reset-*` headers.
+#[derive(Clone, Debug)]
+pub struct RetryPolicy {
+    /// The maximum number of attempts, including the first one.
+    pub max_attempts: u32,
+
+    /// The delay before the first retry, doubled with each following one.
+    pub base_delay: Duration,
+
+    /// The upper bound of any delay, including the ones requested by OpenAI.
+    pub max_delay: Duration,
+
+    /// Whether to randomize delays, so that concurrent clients don't retry in lockstep.
+    pub jitter: bool,
+
+    /// Response status codes considered transient.
+    pub statuses: Vec<StatusCode>,
+
+    /// Whether to retry requests that timed out.
+    pub retry_timeouts: bool,
+
+    /// Whether to retry requests that failed to connect.
+    pub retry_connect_errors: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(500),
+            max_delay: DEFAULT_MAX_DELAY,
+            jitter: true,
+            statuses: vec![
+                StatusCode::REQUEST_TIMEOUT,
+                StatusCode::CONFLICT,
+                StatusCode::TOO_MANY_REQUESTS,
+                StatusCode::INTERNAL_SERVER_ERROR,
+                StatusCode::BAD_GATEWAY,
+                StatusCode::SERVICE_UNAVAILABLE,
+                StatusCode::GATEWAY_TIMEOUT,
+            ],
+            retry_timeouts: true,
+            retry_connect_errors: true,
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts;
+
+        self
+    }
+
+    pub fn base_delay(mut self, base_delay: Duration) -> Self {
+        self.base_delay = base_delay;
+
+        self
+    }
+
+    pub fn max_delay(mut self, max_delay: Duration) -> Self {
+        self.max_delay = max_delay;
+
+        self
+    }
+
+    pub fn jitter(mut self, jitter: bool) -> Self {
+        self.jitter = jitter;
+
+        self
+    }
+
+    pub fn statuses(mut self, statuses: Vec<StatusCode>) -> Self {
+        self.statuses = statuses;
+
+        self
+    }
+
+    pub fn retry_timeouts(mut self, retry_timeouts: bool) -> Self {
+        self.retry_timeouts = retry_timeouts;
+
+        self
+    }
+
+    pub fn retry_connect_errors(mut self, retry_connect_errors: bool) -> Self {
+        self.retry_connect_errors = retry_connect_errors;
+
+        self
+    }
+
+    /// Returns how long to wait before retrying, or `None` if the outcome of the given attempt is final.
+    pub(crate) fn delay(&self, attempt: u32, outcome: &Result<Response>) -> Option<Duration> {
+        if attempt >= self.max_attempts {
+            return None;
+        }
+
+        match outcome {
+            Ok(resp) if self.statuses.contains(&resp.status()) => Some(
+                requested_delay(resp.status(), resp.headers(), self.max_delay)
+                    .unwrap_or_else(|| self.backoff(attempt)),
+            ),
+            Ok(_) => None,
+            Err(Error::Reqwest(err))
+                if (err.is_timeout() && self.retry_timeouts)
+                    || (err.is_connect() && self.retry_connect_errors) =>
+            {
+                Some(self.backoff(attempt))
+            }
+            Err(_) => None,
+        }
+    }
+
+    /// The exponential delay after the given (1-based) attempt.
+    fn backoff(&self, attempt: u32) -> Duration {
+        let exp = 2u32.saturating_pow(attempt.saturating_sub(1));
+        let delay = self.base_delay.saturating_mul(exp).min(self.max_delay);
+
+        if self.jitter {
+            // Keep at least half of the delay, randomizing the rest.
+            delay / 2 + delay.mul_f64(fastrand::f64() / 2.0)
+        } else {
+            delay
+        }
+    }
+}
+
+/// The delay OpenAI asked for, if any, up to `max`.
+///
+/// `Retry-After` is accepted both in seconds and as an HTTP date.
+/// The `x-ratelimit-reset-*` headers are only considered for rate limited requests,
+/// and only for the limits that were actually exhausted. When multiple delays apply, the longest one wins.
+/// Values that aren't finite, or don't fit in a [`Duration`], are ignored.
+pub(crate) fn requested_delay(
+    status: StatusCode,
+    headers: &HeaderMap,
+    max: Duration,
+) -> Option<Duration> {
+    let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok());
+
+    let retry_after = header("retry-after-ms")
+        .and_then(|v| v.parse::<f64>().ok())
+        .and_then(|ms| seconds(ms / 1000.0))
+        .or_else(|| header("retry-after").and_then(parse_retry_after));
+
+    let reset = |limit: &str| {
+        let exhausted =
+            header(&format!("x-ratelimit-remaining-{limit}")).map_or(true, |v| v == "0");
+        if status != StatusCode::TOO_MANY_REQUESTS || !exhausted {
+            return None;
+        }
+
+        header(&format!("x-ratelimit-reset-{limit}")).and_then(parse_duration)
+    };
+
+    [retry_after, reset("requests"), reset("tokens")]
+        .into_iter()
+        .flatten()
+        .max()
+        .map(|d| d.min(max))
+}
+
+/// Parses a `Retry-After` header, either in seconds or as an HTTP date.
+fn parse_retry_after(value: &str) -> Option<Duration> {
+    if let Ok(secs) = value.trim().parse::<f64>() {
+        return seconds(secs);
+    }
+
+    let date = httpdate::parse_http_date(value.trim()).ok()?;
+    Some(
+        date.duration_since(SystemTime::now())
+            .unwrap_or(Duration::ZERO),
+    )
+}
+
+/// The given number of seconds, negative ones counting as zero, unless it isn't finite or too large for a [`Duration`].
+fn seconds(secs: f64) -> Option<Duration> {
+    if !secs.is_finite() {
+        return None;
+    }
+
+    Duration::try_from_secs_f64(secs.max(0.0)).ok()
+}
+
+/// Parses durations in the format OpenAI uses for its rate limit headers, like `1s`, `6m0s` or `20ms`.
+pub(crate) fn parse_duration(s: &str) -> Option<Duration> {
+    let mut total = Duration::ZERO;
+    let mut rest = s.trim();
+
+    if rest.is_empty() {
+        return None;
+    }
+
+    while !rest.is_empty() {
+        let split = rest
+            .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+            .unwrap_or(rest.len());
+        let (value, tail) = rest.split_at(split);
+        let value: f64 = value.parse().ok()?;
+
+        let unit_len = tail
+            .find(|c: char| c.is_ascii_digit() || c == '.')
+            .unwrap_or(tail.len());
+        let (unit, tail) = tail.split_at(unit_len);
+
+        let secs = match unit {
+            "ms" => value / 1000.0,
+            "s" => value,
+            "m" => value * 60.0,
+            "h" => value * 3600.0,
+            _ => return None,
+        };
+
+        total = total.checked_add(seconds(secs)?)?;
+        rest = tail;
+    }
+
+    Some(total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("1s"), Some(Duration::from_secs(1)));
+        assert_eq!(parse_duration("20ms"), Some(Duration::from_millis(20)));
+        assert_eq!(parse_duration("6m0s"), Some(Duration::from_secs(360)));
+        assert_eq!(
+            parse_duration("1h2m3.5s"),
+            Some(Duration::from_secs_f64(3723.5))
+        );
+        assert_eq!(parse_duration(""), None);
+        assert_eq!(parse_duration("soon"), None);
+        assert_eq!(parse_duration("1e30s"), None);
+        assert_eq!(parse_duration(&format!("{}s", "9".repeat(30))), None);
+    }
+
+    #[test]
+    fn test_requested_delay() {
+        let limited = StatusCode::TOO_MANY_REQUESTS;
+        let mut headers = HeaderMap::new();
+        let max = Duration::from_secs(60);
+        assert_eq!(requested_delay(limited, &headers, max), None);
+
+        headers.insert("retry-after", "2".parse().unwrap());
+        headers.insert("x-ratelimit-reset-requests", "500ms".parse().unwrap());
+        headers.insert("x-ratelimit-reset-tokens", "6s".parse().unwrap());
+        assert_eq!(
+            requested_delay(limited, &headers, max),
+            Some(Duration::from_secs(6))
+        );
+
+        // Only the exhausted limit counts.
+        headers.insert("x-ratelimit-remaining-tokens", "1000".parse().unwrap());
+        assert_eq!(
+            requested_delay(limited, &headers, max),
+            Some(Duration::from_secs(2))
+        );
+
+        headers.insert("retry-after-ms", "1500".parse().unwrap());
+        assert_eq!(
+            requested_delay(limited, &headers, max),
+            Some(Duration::from_millis(1500))
+        );
+
+        headers.remove("retry-after-ms");
+        headers.remove("retry-after");
+        assert_eq!(
+            requested_delay(StatusCode::SERVICE_UNAVAILABLE, &headers, max),
+            None
+        );
+    }
+
+    #[test]
+    fn test_requested_delay_bounds() {
+        let limited = StatusCode::TOO_MANY_REQUESTS;
+        let max = Duration::from_secs(60);
+        let delay = |name: &'static str, value: &str| {
+            let mut headers = HeaderMap::new();
+            headers.insert(name, value.parse().unwrap());
+            requested_delay(limited, &headers, max)
+        };
+
+        assert_eq!(delay("retry-after", "inf"), None);
+        assert_eq!(delay("retry-after", "NaN"), None);
+        assert_eq!(delay("retry-after", "1e30"), None);
+        assert_eq!(delay("retry-after-ms", "1e300"), None);
+        assert_eq!(delay("retry-after", "-5"), Some(Duration::ZERO));
+        assert_eq!(delay("retry-after", "3600"), Some(max));
+        assert_eq!(delay("x-ratelimit-reset-tokens", "1e30s"), None);
+        assert_eq!(
+            delay("x-ratelimit-reset-tokens", &format!("{}s", "9".repeat(30))),
+            None
+        );
+
+        let date = httpdate::fmt_http_date(SystemTime::now() + Duration::from_secs(30));
+        let from_date = delay("retry-after", &date).unwrap();
+        assert!(from_date > Duration::from_secs(28) && from_date <= Duration::from_secs(30));
+        assert_eq!(
+            delay("retry-after", "Wed, 21 Oct 2015 07:28:00 GMT"),
+            Some(Duration::ZERO)
+        );
+    }
+
+    #[test]
+    fn test_backoff() {
+        let policy = RetryPolicy::new()
+            .base_delay(Duration::from_secs(1))
+            .max_delay(Duration::from_secs(5))
+            .jitter(false);
+
+        assert_eq!(policy.backoff(1), Duration::from_secs(1));
+        assert_eq!(policy.backoff(2), Duration::from_secs(2));
+        assert_eq!(policy.backoff(3), Duration::from_secs(4));
+        assert_eq!(policy.backoff(4), Duration::from_secs(5));
+
+        let delay = policy.jitter(true).backoff(3);
+        assert!(delay >= Duration::from_secs(2) && delay <= Duration::from_secs(4));
+    }
+}
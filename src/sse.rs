@@ -0,0 +1,239 @@
+//! A decoder for [Server-Sent Events](https://html.spec.whatwg.org/multipage/server-sent-events.html),
+//! the format OpenAI uses for every streamed response.
+//!
+//! The decoder is fed raw chunks as they arrive from the network, so events (and even single lines)
+//! may be split at arbitrary byte boundaries.
+
+use futures::{stream::BoxStream, StreamExt};
+use serde::de::DeserializeOwned;
+
+use crate::{client::Response, error::Error, trace::Trace, transport, Result};
+
+/// The sentinel OpenAI sends as the data of the last event in a stream.
+const DONE: &str = "[DONE]";
+
+/// A single dispatched event.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct Event {
+    /// The event type, if the server specified one.
+    pub event: Option<String>,
+
+    /// The data of the event, with multiple `data:` lines joined by `\n`.
+    pub data: String,
+}
+
+/// Incrementally splits a byte stream into [`Event`]s.
+#[derive(Debug, Default)]
+pub(crate) struct Decoder {
+    /// Bytes received that don't yet form a complete line.
+    buf: Vec<u8>,
+
+    /// The event currently being built.
+    event: Option<String>,
+    data: String,
+    has_data: bool,
+}
+
+impl Decoder {
+    /// Feeds a chunk of bytes to the decoder, returning every event it completed.
+    pub fn feed(&mut self, chunk: &[u8]) -> Vec<Event> {
+        self.buf.extend_from_slice(chunk);
+
+        let mut events = Vec::new();
+        while let Some(line) = self.next_line(false) {
+            events.extend(self.process_line(&line));
+        }
+
+        events
+    }
+
+    /// Flushes whatever is left once the underlying stream is exhausted.
+    ///
+    /// Strictly speaking, an event that isn't terminated by a blank line should be discarded,
+    /// but some servers close the connection right after the last `data:` line.
+    pub fn finish(&mut self) -> Vec<Event> {
+        let mut events = Vec::new();
+        while let Some(line) = self.next_line(true) {
+            events.extend(self.process_line(&line));
+        }
+        events.extend(self.dispatch());
+
+        events
+    }
+
+    /// Takes the next complete line out of the buffer, without its terminator.
+    ///
+    /// Lines may be terminated by `\r\n`, `\n` or `\r`.
+    fn next_line(&mut self, eof: bool) -> Option<String> {
+        let pos = self.buf.iter().position(|&b| b == b'\n' || b == b'\r');
+
+        let (end, skip) = match pos {
+            Some(i) if self.buf[i] == b'\r' => match self.buf.get(i + 1) {
+                Some(b'\n') => (i, 2),
+                Some(_) => (i, 1),
+                // The matching `\n` may still be in flight.
+                None if !eof => return None,
+                None => (i, 1),
+            },
+            Some(i) => (i, 1),
+            None if eof && !self.buf.is_empty() => (self.buf.len(), 0),
+            None => return None,
+        };
+
+        let line = String::from_utf8_lossy(&self.buf[..end]).into_owned();
+        self.buf.drain(..end + skip);
+
+        Some(line)
+    }
+
+    fn process_line(&mut self, line: &str) -> Option<Event> {
+        if line.is_empty() {
+            return self.dispatch();
+        }
+
+        // Comments, frequently used as keep-alives.
+        if line.starts_with(':') {
+            return None;
+        }
+
+        let (field, value) = match line.split_once(':') {
+            Some((field, value)) => (field, value.strip_prefix(' ').unwrap_or(value)),
+            None => (line, ""),
+        };
+
+        match field {
+            "data" => {
+                if self.has_data {
+                    self.data.push('\n');
+                }
+                self.data.push_str(value);
+                self.has_data = true;
+            }
+            "event" => self.event = Some(value.to_string()),
+            // `id` & `retry` are only meaningful for reconnecting, which OpenAI doesn't support.
+            _ => {}
+        }
+
+        None
+    }
+
+    fn dispatch(&mut self) -> Option<Event> {
+        let event = self.event.take();
+        if !std::mem::take(&mut self.has_data) {
+            return None;
+        }
+
+        Some(Event {
+            event,
+            data: std::mem::take(&mut self.data),
+        })
+    }
+}
+
+/// Turns a streamed response into a stream of `T`, ending at the `[DONE]` sentinel.
+///
+/// The response is expected to be successful; errors that OpenAI reports mid-stream are yielded as [`Error::APIError`].
+/// Each event is recorded in the span of the request.
+pub(crate) fn stream<T>(
+    resp: transport::Response,
+    mut trace: Trace,
+) -> BoxStream<'static, Result<T>>
+where
+    T: DeserializeOwned + Send + 'static,
+{
+    let mut bytes = resp.bytes_stream();
+    let stream = async_stream::try_stream! {
+        let mut decoder = Decoder::default();
+        let mut eof = false;
+
+        while !eof {
+            let events = match bytes.next().await {
+                Some(chunk) => decoder.feed(&chunk?),
+                None => {
+                    eof = true;
+                    decoder.finish()
+                }
+            };
+
+            for event in events {
+                if event.data == DONE {
+                    return;
+                }
+                trace.event(&event.data);
+
+                yield parse::<T>(&event.data)?;
+            }
+        }
+    };
+
+    stream.boxed()
+}
+
+fn parse<T: DeserializeOwned>(data: &str) -> Result<T> {
+    match serde_json::from_str::<Response<T>>(data)? {
+        Response::Invalid(err) => Err(Error::APIError(err)),
+        Response::Valid(val) => Ok(val),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn data(events: &[Event]) -> Vec<&str> {
+        events.iter().map(|e| e.data.as_str()).collect()
+    }
+
+    #[test]
+    fn test_decode_single_chunk() {
+        let mut decoder = Decoder::default();
+        let events = decoder.feed(b"data: {\"a\":1}\n\ndata: {\"a\":2}\n\ndata: [DONE]\n\n");
+
+        assert_eq!(data(&events), vec!["{\"a\":1}", "{\"a\":2}", "[DONE]"]);
+    }
+
+    #[test]
+    fn test_decode_split_across_chunks() {
+        let raw = "data: {\"content\":\"héllo\"}\r\n\r\ndata: [DONE]\r\n\r\n".as_bytes();
+
+        // Feed one byte at a time, splitting both the line terminators and the multi-byte character.
+        let mut decoder = Decoder::default();
+        let events: Vec<Event> = raw.chunks(1).flat_map(|c| decoder.feed(c)).collect();
+
+        assert_eq!(data(&events), vec!["{\"content\":\"héllo\"}", "[DONE]"]);
+        assert!(decoder.finish().is_empty());
+    }
+
+    #[test]
+    fn test_decode_multiline_data_and_comments() {
+        let mut decoder = Decoder::default();
+        let events =
+            decoder.feed(b": keep-alive\n\nevent: delta\ndata: first\ndata:second\nid: 1\n\n");
+
+        assert_eq!(
+            events,
+            vec![Event {
+                event: Some("delta".to_string()),
+                data: "first\nsecond".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_decode_unterminated_event() {
+        let mut decoder = Decoder::default();
+
+        assert!(decoder.feed(b"data: last").is_empty());
+        assert_eq!(data(&decoder.finish()), vec!["last"]);
+    }
+
+    #[test]
+    fn test_parse_error_event() {
+        let err = parse::<serde_json::Value>(
+            r#"{"error": {"message": "overloaded", "type": "server_error", "param": null, "code": null}}"#,
+        )
+        .unwrap_err();
+
+        assert!(matches!(err, Error::APIError(e) if e.error.message == "overloaded"));
+    }
+}
@@ -0,0 +1,541 @@
+//! Recording real traffic once, and replaying it in tests without network access.
+//!
+//! A [`Cassette`] is a [`Middleware`]: while recording, it lets each request through,
+//! and writes it together with its response to a JSON file. While replaying, it answers each request
+//! with the response recorded for it, without ever sending it.
+//!
+//! Requests are matched by their method, path, query and body. JSON bodies are compared regardless of formatting
+//! and key order; multipart uploads are recorded as the name, file name, size & hash of each of their fields,
+//! and compared on those, regardless of their random boundary. Any other body is compared as text, byte for byte.
+//! Requests that weren't recorded fail with an [`Error::Middleware`].
+//!
+//! Credentials, like the API key or the organization, are redacted from the cassette, in requests & responses alike,
+//! and cookies set by responses are left out.
+//!
+//! ## Usage
+//! ```no_run
+//! use fieri::{testing::cassette::Cassette, Client};
+//!
+//! # fn main() -> Result<(), fieri::Error> {
+//! // Once, with network access and a real API key.
+//! let client = Client::new().middleware(Cassette::record("tests/cassettes/chat.json"));
+//!
+//! // In CI.
+//! let client = Client::new().middleware(Cassette::replay("tests/cassettes/chat.json")?);
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use futures::future::BoxFuture;
+use http::header::{HeaderName, HeaderValue, CONTENT_TYPE};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::{MockResponse, ReceivedRequest};
+use crate::{
+    cache::fnv1a,
+    middleware::{Middleware, Next, Request, Response},
+    Error, Result,
+};
+
+/// Headers carrying credentials, never written to a cassette.
+const REDACTED_HEADERS: &[&str] = &["authorization", "api-key", "openai-organization"];
+
+/// Response headers left out of cassettes altogether.
+const DROPPED_HEADERS: &[&str] = &["set-cookie"];
+
+const REDACTED: &str = "[REDACTED]";
+
+/// Records requests & their responses to a file, or replays them from it.
+#[derive(Clone, Debug)]
+pub struct Cassette {
+    path: PathBuf,
+    mode: Mode,
+    state: Arc<Mutex<State>>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Mode {
+    Record,
+    Replay,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    tape: Tape,
+
+    /// Whether each interaction has already been replayed.
+    played: Vec<bool>,
+}
+
+/// The contents of a cassette file.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+struct Tape {
+    interactions: Vec<Interaction>,
+}
+
+/// A recorded request, and its response.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Interaction {
+    pub request: RecordedRequest,
+    pub response: RecordedResponse,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct RecordedRequest {
+    pub method: String,
+    pub path: String,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub query: Option<String>,
+
+    /// The headers of the request, with credentials redacted.
+    #[serde(default)]
+    pub headers: Vec<(String, String)>,
+
+    /// The body, parsed as JSON if possible, or `None` for multipart uploads.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body: Option<Value>,
+
+    /// The fields of multipart uploads, in order.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub multipart: Option<Vec<RecordedPart>>,
+}
+
+/// A field of a multipart upload, without its contents.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct RecordedPart {
+    pub name: String,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub filename: Option<String>,
+
+    /// The size of the contents, in bytes.
+    pub size: usize,
+
+    /// A hash of the contents.
+    pub hash: String,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct RecordedResponse {
+    pub status: u16,
+
+    /// The headers of the response, with credentials redacted & cookies left out.
+    #[serde(default)]
+    pub headers: Vec<(String, String)>,
+
+    /// The whole body, including every event of streamed responses.
+    pub body: String,
+}
+
+impl Cassette {
+    /// Records every request, and its response, to the file at the given path.
+    ///
+    /// The file is written after each request, replacing any previous recording.
+    pub fn record(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            mode: Mode::Record,
+            state: Arc::default(),
+        }
+    }
+
+    /// Replays the requests recorded in the file at the given path.
+    pub fn replay(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let tape: Tape = serde_json::from_slice(&fs::read(&path)?)?;
+
+        Ok(Self {
+            path,
+            mode: Mode::Replay,
+            state: Arc::new(Mutex::new(State {
+                played: vec![false; tape.interactions.len()],
+                tape,
+            })),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Every interaction recorded so far, or loaded from the file.
+    pub fn interactions(&self) -> Vec<Interaction> {
+        self.state.lock().unwrap().tape.interactions.clone()
+    }
+
+    async fn record_one(
+        &self,
+        request: RecordedRequest,
+        next: Next<'_>,
+        raw: Request,
+    ) -> Result<Response> {
+        let resp = next.run(raw).await?;
+        let response = RecordedResponse {
+            status: resp.status().as_u16(),
+            headers: redact(
+                resp.headers()
+                    .iter()
+                    .filter(|(k, _)| !DROPPED_HEADERS.contains(&k.as_str())),
+            ),
+            body: String::from_utf8_lossy(&resp.bytes().await?).into_owned(),
+        };
+
+        {
+            let mut state = self.state.lock().unwrap();
+            state.tape.interactions.push(Interaction {
+                request,
+                response: response.clone(),
+            });
+
+            if let Some(dir) = self.path.parent().filter(|d| !d.as_os_str().is_empty()) {
+                fs::create_dir_all(dir)?;
+            }
+            fs::write(&self.path, serde_json::to_vec_pretty(&state.tape)?)?;
+        }
+
+        Ok(response.into_response())
+    }
+
+    fn replay_one(&self, request: &RecordedRequest) -> Result<Response> {
+        let mut state = self.state.lock().unwrap();
+        let State { tape, played } = &mut *state;
+
+        let position = tape
+            .interactions
+            .iter()
+            .zip(played.iter())
+            .position(|(i, played)| !played && i.request.matches(request));
+
+        match position {
+            Some(i) => {
+                played[i] = true;
+                Ok(tape.interactions[i].response.clone().into_response())
+            }
+            None => Err(Error::Middleware(
+                format!(
+                    "No recorded interaction for {} {}{} in {}.",
+                    request.method,
+                    request.path,
+                    request
+                        .query
+                        .as_deref()
+                        .map(|q| format!("?{q}"))
+                        .unwrap_or_default(),
+                    self.path.display()
+                )
+                .into(),
+            )),
+        }
+    }
+}
+
+impl Middleware for Cassette {
+    fn handle<'a>(&'a self, request: Request, next: Next<'a>) -> BoxFuture<'a, Result<Response>> {
+        let recorded = RecordedRequest::from(&request);
+
+        Box::pin(async move {
+            match self.mode {
+                Mode::Record => self.record_one(recorded, next, request).await,
+                Mode::Replay => self.replay_one(&recorded),
+            }
+        })
+    }
+}
+
+impl RecordedRequest {
+    fn matches(&self, other: &RecordedRequest) -> bool {
+        self.method == other.method
+            && self.path == other.path
+            && self.query == other.query
+            && self.body == other.body
+            && self.multipart == other.multipart
+    }
+}
+
+impl From<&Request> for RecordedRequest {
+    fn from(request: &Request) -> Self {
+        let received = ReceivedRequest::from(request);
+        let multipart = received.body.as_deref().and_then(|b| {
+            let content_type = received.headers.get(CONTENT_TYPE)?.to_str().ok()?;
+            parse_multipart(b, boundary(content_type)?)
+        });
+        let body = received
+            .body
+            .as_deref()
+            .filter(|_| multipart.is_none())
+            .map(|b| {
+                serde_json::from_slice(b)
+                    .unwrap_or_else(|_| Value::String(String::from_utf8_lossy(b).into_owned()))
+            });
+
+        Self {
+            method: received.method.to_string(),
+            path: received.path,
+            query: received.query,
+            headers: redact(received.headers.iter()),
+            body,
+            multipart,
+        }
+    }
+}
+
+/// The given headers, with the values of credentials redacted.
+fn redact<'a>(
+    headers: impl Iterator<Item = (&'a HeaderName, &'a HeaderValue)>,
+) -> Vec<(String, String)> {
+    headers
+        .map(|(k, v)| {
+            let value = match REDACTED_HEADERS.contains(&k.as_str()) {
+                true => REDACTED.to_string(),
+                false => String::from_utf8_lossy(v.as_bytes()).into_owned(),
+            };
+
+            (k.to_string(), value)
+        })
+        .collect()
+}
+
+/// The boundary of a `multipart/form-data` content type.
+fn boundary(content_type: &str) -> Option<&str> {
+    let (mime, params) = content_type.split_once(';')?;
+    if !mime.trim().eq_ignore_ascii_case("multipart/form-data") {
+        return None;
+    }
+
+    params.split(';').find_map(|p| {
+        let (key, value) = p.split_once('=')?;
+        key.trim()
+            .eq_ignore_ascii_case("boundary")
+            .then(|| value.trim().trim_matches('"'))
+    })
+}
+
+/// Parses the fields of a multipart body, or returns `None` if it's malformed.
+fn parse_multipart(body: &[u8], boundary: &str) -> Option<Vec<RecordedPart>> {
+    let delimiter = format!("\r\n--{boundary}");
+    let mut rest = body.strip_prefix(&delimiter.as_bytes()[2..])?;
+    let mut parts = Vec::new();
+
+    // Each part follows a delimiter, until the closing one ending with `--`.
+    while !rest.starts_with(b"--") {
+        rest = rest.strip_prefix(b"\r\n")?;
+        let end = find(rest, delimiter.as_bytes())?;
+        let (part, tail) = rest.split_at(end);
+        rest = &tail[delimiter.len()..];
+
+        let split = find(part, b"\r\n\r\n")?;
+        let head = std::str::from_utf8(&part[..split]).ok()?;
+        let contents = &part[split + 4..];
+
+        let disposition = head.split("\r\n").find_map(|line| {
+            let (name, value) = line.split_once(':')?;
+            name.eq_ignore_ascii_case("content-disposition")
+                .then_some(value)
+        })?;
+
+        parts.push(RecordedPart {
+            name: param(disposition, "name")?,
+            filename: param(disposition, "filename"),
+            size: contents.len(),
+            hash: format!("{:032x}", fnv1a(&[contents])),
+        });
+    }
+
+    Some(parts)
+}
+
+/// The value of a quoted parameter of a `Content-Disposition` header.
+fn param(disposition: &str, key: &str) -> Option<String> {
+    let start = disposition.find(&format!("; {key}=\""))? + key.len() + 4;
+    let len = disposition[start..].find('"')?;
+
+    Some(disposition[start..start + len].to_string())
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
+
+impl RecordedResponse {
+    /// Rebuilds the response, sending streamed bodies one event at a time.
+    fn into_response(self) -> Response {
+        let chunks = self
+            .body
+            .split_inclusive("\n\n")
+            .map(|c| c.as_bytes().to_vec())
+            .collect();
+
+        MockResponse {
+            status: self.status,
+            headers: self.headers,
+            chunks,
+        }
+        .into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        chat::{chat, chat_stream},
+        file::upload,
+        model::list,
+        multipart::{Form, Part},
+        testing::{Method, MockTransport},
+        types::{ChatMessageBuilder, ChatParam, ChatParamBuilder, ChatStreamAccumulator, Purpose},
+        Client,
+    };
+
+    fn param(content: &str) -> ChatParam {
+        let message = ChatMessageBuilder::new("user", content).build().unwrap();
+
+        ChatParamBuilder::new("gpt-3.5-turbo", vec![message])
+            .build()
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_record_replay() {
+        let path = std::env::temp_dir().join(format!("fieri-cassette-{}.json", fastrand::u64(..)));
+
+        let recording = MockTransport::with_fixtures();
+        let client = recording
+            .client()
+            .api_key("sk-secret")
+            .middleware(Cassette::record(&path));
+        let recorded = chat(&client, &param("Hello!")).await.unwrap();
+        let stream = chat_stream(&client, &param("Hello!")).await.unwrap();
+        let recorded_stream = ChatStreamAccumulator::collect(stream).await.unwrap();
+
+        let contents = fs::read_to_string(&path).unwrap();
+        assert!(!contents.contains("sk-secret"));
+        assert!(contents.contains(REDACTED));
+
+        // Nothing reaches the transport while replaying.
+        let replaying = MockTransport::new();
+        let client = Client::builder()
+            .transport(replaying.clone())
+            .middleware(Cassette::replay(&path).unwrap())
+            .build()
+            .unwrap();
+
+        let stream = chat_stream(&client, &param("Hello!")).await.unwrap();
+        let replayed_stream = ChatStreamAccumulator::collect(stream).await.unwrap();
+        let replayed = chat(&client, &param("Hello!")).await.unwrap();
+
+        assert_eq!(
+            replayed.choices[0].message.content,
+            recorded.choices[0].message.content
+        );
+        assert_eq!(
+            replayed_stream.choices[0].message.content,
+            recorded_stream.choices[0].message.content
+        );
+        assert!(replaying.requests().is_empty());
+
+        // Each interaction is only replayed once, and different bodies don't match.
+        let err = chat(&client, &param("Hello!")).await.unwrap_err();
+        assert!(
+            matches!(err, Error::Middleware(e) if e.to_string().starts_with("No recorded interaction for POST /v1/chat/completions"))
+        );
+        assert!(chat(&client, &param("Bye!")).await.is_err());
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_record_redacts_responses() {
+        let path = std::env::temp_dir().join(format!("fieri-cassette-{}.json", fastrand::u64(..)));
+
+        let mock = MockTransport::new();
+        mock.once(
+            Method::GET,
+            "models",
+            MockResponse::json(serde_json::json!({"object": "list", "data": []}))
+                .header("openai-organization", "org-secret")
+                .header("set-cookie", "__cf_bm=secret; path=/")
+                .header("x-request-id", "req_123"),
+        );
+        let client = mock.client().middleware(Cassette::record(&path));
+        list(&client).await.unwrap();
+
+        let contents = fs::read_to_string(&path).unwrap();
+        assert!(!contents.contains("org-secret"));
+        assert!(!contents.contains("set-cookie"));
+        assert!(contents.contains("req_123"));
+
+        let headers = &Cassette::replay(&path).unwrap().interactions()[0]
+            .response
+            .headers;
+        assert!(headers.contains(&("openai-organization".to_string(), REDACTED.to_string())));
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_record_replay_upload() {
+        let path = std::env::temp_dir().join(format!("fieri-cassette-{}.json", fastrand::u64(..)));
+        let readme = concat!(env!("CARGO_MANIFEST_DIR"), "/README.md");
+        let manifest = concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.toml");
+
+        let client = MockTransport::with_fixtures()
+            .client()
+            .middleware(Cassette::record(&path));
+        upload(&client, readme, Purpose::FineTune).await.unwrap();
+
+        let cassette = Cassette::replay(&path).unwrap();
+        let request = &cassette.interactions()[0].request;
+        let parts = request.multipart.as_ref().unwrap();
+        assert_eq!(request.body, None);
+        assert_eq!(
+            parts
+                .iter()
+                .map(|p| (p.name.as_str(), p.filename.as_deref()))
+                .collect::<Vec<_>>(),
+            [("file", Some(readme)), ("purpose", None)]
+        );
+        assert_eq!(parts[0].size, fs::metadata(readme).unwrap().len() as usize);
+
+        // Uploads match regardless of their boundary, but not with other files.
+        let client = Client::builder()
+            .transport(MockTransport::new())
+            .middleware(cassette)
+            .build()
+            .unwrap();
+        assert!(upload(&client, manifest, Purpose::FineTune).await.is_err());
+        upload(&client, readme, Purpose::FineTune).await.unwrap();
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_parse_multipart() {
+        let form = Form::new()
+            .part(
+                "file",
+                Part::bytes(&b"{}"[..]).file_name("a; name=\"b\".jsonl"),
+            )
+            .text("purpose", "fine-tune");
+        let content_type = form.content_type();
+
+        let parts = parse_multipart(&form.encode(), boundary(&content_type).unwrap()).unwrap();
+        assert_eq!(parts[0].name, "file");
+        assert_eq!(parts[0].filename.as_deref(), Some("a; name=%22b%22.jsonl"));
+        assert_eq!(parts[0].size, 2);
+        assert_eq!(parts[1].name, "purpose");
+        assert_eq!(parts[1].size, 9);
+        assert_ne!(parts[0].hash, parts[1].hash);
+
+        assert_eq!(boundary("application/json"), None);
+        assert_eq!(parse_multipart(b"{}", "x"), None);
+    }
+}
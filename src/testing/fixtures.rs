@@ -0,0 +1,264 @@
+//! Canned, spec-shaped responses for each endpoint.
+
+use serde_json::{json, Value};
+
+use super::{Method, MockResponse, ReceivedRequest, BASE_PATH};
+
+/// The time every fixture was created at.
+pub const CREATED: u64 = 1_700_000_000;
+
+/// Answers the given request with the fixture of its endpoint, if there's one.
+pub fn respond(request: &ReceivedRequest) -> Option<MockResponse> {
+    let path = request.path.strip_prefix(BASE_PATH)?;
+    let segments: Vec<&str> = path.trim_end_matches('/').split('/').collect();
+    let model = request
+        .json()
+        .and_then(|b| b["model"].as_str().map(String::from))
+        .unwrap_or_else(|| "gpt-3.5-turbo".to_string());
+    let stream = request.is_stream();
+
+    let body = match (&request.method, segments.as_slice()) {
+        (&Method::POST, ["chat", "completions"]) if stream => {
+            return Some(MockResponse::sse(chat_chunks(&model)))
+        }
+        (&Method::POST, ["chat", "completions"]) => chat(&model),
+        (&Method::POST, ["completions"]) if stream => {
+            return Some(MockResponse::sse(completion_chunks(&model)))
+        }
+        (&Method::POST, ["completions"]) => completion(&model),
+        (&Method::POST, ["edits"]) => edit(),
+        (&Method::POST, ["embeddings"]) => embedding(&model),
+        (&Method::GET, ["files"]) => files(),
+        (&Method::POST, ["files"]) => file("file-abc123"),
+        (&Method::GET, ["files", id]) => file(id),
+        (&Method::DELETE, ["files", id]) => deleted(id, "file"),
+        (&Method::GET, ["fine-tunes"]) => fine_tunes(),
+        (&Method::POST, ["fine-tunes"]) => fine_tune("ft-abc123", "pending"),
+        (&Method::GET, ["fine-tunes", id]) => fine_tune(id, "succeeded"),
+        (&Method::POST, ["fine-tunes", id, "cancel"]) => fine_tune(id, "cancelled"),
+        (&Method::GET, ["fine-tunes", _, "events"]) if stream => {
+            return Some(MockResponse::sse(fine_tune_events()))
+        }
+        (&Method::GET, ["fine-tunes", _, "events"]) => list(fine_tune_events()),
+        (&Method::POST, ["images", "generations" | "edits" | "variations"]) => image(),
+        (&Method::GET, ["models"]) => models(),
+        (&Method::GET, ["models", id]) => self::model(id),
+        (&Method::DELETE, ["models", id]) => deleted(id, "model"),
+        (&Method::POST, ["moderations"]) => moderation(),
+        _ => return None,
+    };
+
+    Some(MockResponse::json(body))
+}
+
+fn list(data: Vec<Value>) -> Value {
+    json!({"object": "list", "data": data})
+}
+
+pub fn chat(model: &str) -> Value {
+    json!({
+        "id": "chatcmpl-abc123",
+        "object": "chat.completion",
+        "created": CREATED,
+        "model": model,
+        "system_fingerprint": "fp_mock",
+        "choices": [{
+            "index": 0,
+            "message": {"role": "assistant", "content": "Hello! How can I help you today?"},
+            "finish_reason": "stop"
+        }],
+        "usage": {"prompt_tokens": 9, "completion_tokens": 9, "total_tokens": 18}
+    })
+}
+
+/// The chunks of a streamed [`chat`], one per word.
+pub fn chat_chunks(model: &str) -> Vec<Value> {
+    let chunk = |delta: Value, finish_reason: Option<&str>| {
+        json!({
+            "id": "chatcmpl-abc123",
+            "object": "chat.completion.chunk",
+            "created": CREATED,
+            "model": model,
+            "system_fingerprint": "fp_mock",
+            "choices": [{"index": 0, "delta": delta, "finish_reason": finish_reason}]
+        })
+    };
+
+    let mut chunks = vec![chunk(json!({"role": "assistant", "content": ""}), None)];
+    chunks.extend(
+        ["Hello!", " How", " can", " I", " help", " you", " today?"]
+            .iter()
+            .map(|word| chunk(json!({"content": word}), None)),
+    );
+    chunks.push(chunk(json!({}), Some("stop")));
+
+    chunks
+}
+
+pub fn completion(model: &str) -> Value {
+    json!({
+        "id": "cmpl-abc123",
+        "object": "text_completion",
+        "created": CREATED,
+        "model": model,
+        "choices": [{
+            "text": "\n\nThis is indeed a test",
+            "index": 0,
+            "logprobs": null,
+            "finish_reason": "length"
+        }],
+        "usage": {"prompt_tokens": 5, "completion_tokens": 7, "total_tokens": 12}
+    })
+}
+
+/// The chunks of a streamed [`completion`], one per word.
+pub fn completion_chunks(model: &str) -> Vec<Value> {
+    ["\n\nThis", " is", " indeed", " a", " test"]
+        .iter()
+        .enumerate()
+        .map(|(i, text)| {
+            json!({
+                "id": "cmpl-abc123",
+                "object": "text_completion",
+                "created": CREATED,
+                "model": model,
+                "choices": [{
+                    "text": text,
+                    "index": 0,
+                    "logprobs": null,
+                    "finish_reason": if i == 4 { json!("length") } else { Value::Null }
+                }]
+            })
+        })
+        .collect()
+}
+
+pub fn edit() -> Value {
+    json!({
+        "object": "edit",
+        "created": CREATED,
+        "choices": [{"text": "What day of the week is it?", "index": 0}],
+        "usage": {"prompt_tokens": 25, "completion_tokens": 32, "total_tokens": 57}
+    })
+}
+
+pub fn embedding(model: &str) -> Value {
+    json!({
+        "object": "list",
+        "data": [{"object": "embedding", "embedding": [0.0023064255, -0.009327292, -0.0028842222], "index": 0}],
+        "model": model,
+        "usage": {"prompt_tokens": 8, "completion_tokens": 0, "total_tokens": 8}
+    })
+}
+
+pub fn file(id: &str) -> Value {
+    json!({
+        "id": id,
+        "object": "file",
+        "bytes": 140,
+        "created_at": CREATED,
+        "filename": "mydata.jsonl",
+        "purpose": "fine-tune",
+        "status": "processed"
+    })
+}
+
+pub fn files() -> Value {
+    list(vec![file("file-abc123"), file("file-def456")])
+}
+
+pub fn deleted(id: &str, object: &str) -> Value {
+    json!({"id": id, "object": object, "deleted": true})
+}
+
+pub fn fine_tune(id: &str, status: &str) -> Value {
+    json!({
+        "id": id,
+        "object": "fine-tune",
+        "model": "curie",
+        "created_at": CREATED,
+        "events": fine_tune_events(),
+        "fine_tuned_model": if status == "succeeded" { json!("curie:ft-acmeco-2021-03-03-21-44-20") } else { Value::Null },
+        "hyperparams": {
+            "batch_size": 4,
+            "learning_rate_multiplier": 0.1,
+            "n_epochs": 4,
+            "prompt_loss_weight": 0.1
+        },
+        "organization_id": "org-abc123",
+        "result_files": [],
+        "status": status,
+        "validation_files": [],
+        "training_files": [file("file-abc123")],
+        "updated_at": CREATED
+    })
+}
+
+pub fn fine_tunes() -> Value {
+    list(vec![fine_tune("ft-abc123", "succeeded")])
+}
+
+pub fn fine_tune_events() -> Vec<Value> {
+    ["Created fine-tune: ft-abc123", "Fine-tune succeeded"]
+        .iter()
+        .map(|message| {
+            json!({
+                "object": "fine-tune-event",
+                "created_at": CREATED,
+                "level": "info",
+                "message": message
+            })
+        })
+        .collect()
+}
+
+pub fn image() -> Value {
+    json!({
+        "created": CREATED,
+        "data": [{"url": "https://example.com/image.png"}]
+    })
+}
+
+pub fn model(id: &str) -> Value {
+    json!({
+        "id": id,
+        "object": "model",
+        "created": CREATED,
+        "owned_by": "openai",
+        "permission": [],
+        "root": id,
+        "parent": null
+    })
+}
+
+pub fn models() -> Value {
+    list(vec![model("gpt-3.5-turbo"), model("gpt-4")])
+}
+
+pub fn moderation() -> Value {
+    json!({
+        "id": "modr-abc123",
+        "model": "text-moderation-007",
+        "results": [{
+            "flagged": true,
+            "categories": {
+                "hate": false,
+                "hate/threatening": false,
+                "self-harm": false,
+                "sexual": false,
+                "sexual/minors": false,
+                "violence": true,
+                "violence/graphic": false
+            },
+            "category_scores": {
+                "hate": 0.22714105248451233,
+                "hate/threatening": 0.4132447838783264,
+                "self-harm": 0.005232391878962517,
+                "sexual": 0.01407341007143259,
+                "sexual/minors": 0.0038522258400917053,
+                "violence": 0.9223177433013916,
+                "violence/graphic": 0.036865197122097015
+            }
+        }]
+    })
+}
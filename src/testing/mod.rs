@@ -0,0 +1,398 @@
+//! Utilities to test code built on fieri, without network access.
+//!
+//! [`MockTransport`] plugs into a [`Client`] in place of the network, answering each request
+//! with the first matching mock, and keeping track of every request it received.
+//! Created with [`MockTransport::with_fixtures`], requests without a matching mock are answered
+//! with canned, spec-shaped [`fixtures`] for each endpoint, streamed or not.
+//!
+//! Mock paths are relative to `/v1/`, like `chat/completions`, unless they start with a `/`.
+//! A `*` matches any single segment, like `files/*`.
+//!
+//! Only available with the `testing` feature.
+//!
+//! ## Usage
+//! ```
+//! use fieri::{
+//!     chat::chat,
+//!     testing::{Method, MockResponse, MockTransport},
+//!     types::{ChatMessageBuilder, ChatParamBuilder},
+//!     Error,
+//! };
+//!
+//! # #[tokio::main]
+//! # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let mock = MockTransport::with_fixtures();
+//! mock.once(
+//!     Method::POST,
+//!     "chat/completions",
+//!     MockResponse::error(429, "Rate limit reached.", "requests", None).header("retry-after", "1"),
+//! );
+//!
+//! let client = mock.client();
+//! let message = ChatMessageBuilder::new("user", "Hello!").build()?;
+//! let param = ChatParamBuilder::new("gpt-3.5-turbo", vec![message]).build()?;
+//!
+//! assert!(matches!(chat(&client, &param).await, Err(Error::RateLimited { .. })));
+//! assert!(chat(&client, &param).await.is_ok());
+//!
+//! let requests = mock.requests();
+//! assert_eq!(requests.len(), 2);
+//! assert_eq!(requests[0].json().unwrap()["model"], "gpt-3.5-turbo");
+//! # Ok(())
+//! # }
+//! ```
+
+pub mod cassette;
+pub mod fixtures;
+
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use futures::future::BoxFuture;
+use http::HeaderMap;
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use crate::{
+    transport::{Body, Request, Response, Transport},
+    Client, Result,
+};
+
+pub use http::Method;
+
+/// The path mocks are relative to.
+const BASE_PATH: &str = "/v1/";
+
+/// A [`Transport`] answering requests with mocked responses.
+///
+/// Clones share the same mocks & received requests.
+#[derive(Clone, Debug, Default)]
+pub struct MockTransport {
+    state: Arc<Mutex<State>>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    mocks: Vec<Mock>,
+    requests: Vec<ReceivedRequest>,
+
+    /// Whether to answer requests without a matching mock with [`fixtures`].
+    fixtures: bool,
+}
+
+#[derive(Debug)]
+struct Mock {
+    method: Method,
+    path: String,
+    response: MockResponse,
+
+    /// How many more requests the mock answers, if limited.
+    remaining: Option<usize>,
+}
+
+impl MockTransport {
+    /// Creates a transport answering requests without a matching mock with `404 Not Found`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a transport answering requests without a matching mock with [`fixtures`].
+    pub fn with_fixtures() -> Self {
+        let mock = Self::default();
+        mock.state.lock().unwrap().fixtures = true;
+
+        mock
+    }
+
+    /// Answers every matching request with the given response.
+    pub fn on(&self, method: Method, path: impl Into<String>, response: MockResponse) -> &Self {
+        self.mock(method, path.into(), response, None)
+    }
+
+    /// Answers the next matching request with the given response.
+    ///
+    /// Mocks are matched in the order they were added, so consecutive calls answer consecutive requests.
+    pub fn once(&self, method: Method, path: impl Into<String>, response: MockResponse) -> &Self {
+        self.mock(method, path.into(), response, Some(1))
+    }
+
+    fn mock(
+        &self,
+        method: Method,
+        path: String,
+        response: MockResponse,
+        remaining: Option<usize>,
+    ) -> &Self {
+        self.state.lock().unwrap().mocks.push(Mock {
+            method,
+            path,
+            response,
+            remaining,
+        });
+
+        self
+    }
+
+    /// Creates a [`Client`] sending its requests to this transport.
+    pub fn client(&self) -> Client {
+        Client::builder()
+            .api_key("sk-test")
+            .transport(self.clone())
+            .build()
+            .expect("Err creating mock client.")
+    }
+
+    /// Every request received so far, in order.
+    pub fn requests(&self) -> Vec<ReceivedRequest> {
+        self.state.lock().unwrap().requests.clone()
+    }
+
+    /// Panics unless a request with the given method & path has been received.
+    #[track_caller]
+    pub fn assert_requested(&self, method: Method, path: &str) -> ReceivedRequest {
+        let requests = self.requests();
+
+        match requests
+            .iter()
+            .rev()
+            .find(|r| r.method == method && matches(path, &r.path))
+        {
+            Some(request) => request.clone(),
+            None => panic!(
+                "Expected a {method} {path} request, received: {:?}",
+                requests
+                    .iter()
+                    .map(|r| format!("{} {}", r.method, r.path))
+                    .collect::<Vec<_>>()
+            ),
+        }
+    }
+
+    fn respond(&self, request: &ReceivedRequest) -> MockResponse {
+        let mut state = self.state.lock().unwrap();
+        state.requests.push(request.clone());
+
+        let mock = state.mocks.iter_mut().find(|m| {
+            m.remaining != Some(0) && m.method == request.method && matches(&m.path, &request.path)
+        });
+        if let Some(mock) = mock {
+            if let Some(remaining) = &mut mock.remaining {
+                *remaining -= 1;
+            }
+
+            return mock.response.clone();
+        }
+
+        state
+            .fixtures
+            .then(|| fixtures::respond(request))
+            .flatten()
+            .unwrap_or_else(|| {
+                MockResponse::error(
+                    404,
+                    &format!(
+                        "Unrecognized request URL ({}: {}).",
+                        request.method, request.path
+                    ),
+                    "invalid_request_error",
+                    None,
+                )
+            })
+    }
+}
+
+impl Transport for MockTransport {
+    fn send(&self, request: Request) -> BoxFuture<'_, Result<Response>> {
+        let request = ReceivedRequest::from(&request);
+        let response = self.respond(&request);
+
+        Box::pin(async move { Ok(response.into_response()) })
+    }
+}
+
+/// Whether the mock path matches the path of a request.
+fn matches(mock: &str, path: &str) -> bool {
+    let mock = match mock.starts_with('/') {
+        true => mock.to_string(),
+        false => format!("{BASE_PATH}{mock}"),
+    };
+
+    let mock: Vec<&str> = mock.trim_matches('/').split('/').collect();
+    let path: Vec<&str> = path.trim_matches('/').split('/').collect();
+
+    mock.len() == path.len() && mock.iter().zip(&path).all(|(m, p)| *m == "*" || m == p)
+}
+
+/// A request received by a [`MockTransport`].
+#[derive(Clone, Debug)]
+pub struct ReceivedRequest {
+    pub method: Method,
+
+    /// The path of the URL, like `/v1/chat/completions`.
+    pub path: String,
+    pub query: Option<String>,
+    pub headers: HeaderMap,
+
+    /// The body of the request, unless it was empty or streamed.
+    pub body: Option<Vec<u8>>,
+    pub timeout: Option<Duration>,
+}
+
+impl ReceivedRequest {
+    /// The body of the request, parsed as JSON.
+    pub fn json(&self) -> Option<Value> {
+        serde_json::from_slice(self.body.as_deref()?).ok()
+    }
+
+    /// Whether the request asked for a streamed response.
+    pub fn is_stream(&self) -> bool {
+        self.json().is_some_and(|b| b["stream"] == true)
+            || self
+                .query
+                .as_deref()
+                .is_some_and(|q| q.split('&').any(|p| p == "stream=true"))
+    }
+}
+
+impl From<&Request> for ReceivedRequest {
+    fn from(request: &Request) -> Self {
+        Self {
+            method: request.method().clone(),
+            path: request.url().path().to_string(),
+            query: request.url().query().map(String::from),
+            headers: request.headers().clone(),
+            body: request
+                .body()
+                .as_bytes()
+                .filter(|b| !b.is_empty())
+                .map(|b| b.to_vec()),
+            timeout: request.timeout(),
+        }
+    }
+}
+
+/// A mocked response.
+#[derive(Clone, Debug)]
+pub struct MockResponse {
+    status: u16,
+    headers: Vec<(String, String)>,
+
+    /// The body, sent as separate chunks.
+    chunks: Vec<Vec<u8>>,
+}
+
+impl MockResponse {
+    /// A successful response with the given JSON body.
+    pub fn json(body: impl Serialize) -> Self {
+        Self {
+            status: 200,
+            headers: vec![("content-type".to_string(), "application/json".to_string())],
+            chunks: vec![serde_json::to_vec(&body).expect("Err serializing mock response.")],
+        }
+    }
+
+    /// A successful, streamed, response sending each of the given events, followed by `[DONE]`.
+    pub fn sse<T: Serialize>(events: impl IntoIterator<Item = T>) -> Self {
+        let mut chunks: Vec<Vec<u8>> = events
+            .into_iter()
+            .map(|e| {
+                let data = serde_json::to_string(&e).expect("Err serializing mock event.");
+                format!("data: {data}\n\n").into_bytes()
+            })
+            .collect();
+        chunks.push(b"data: [DONE]\n\n".to_vec());
+
+        Self {
+            status: 200,
+            headers: vec![("content-type".to_string(), "text/event-stream".to_string())],
+            chunks,
+        }
+    }
+
+    /// An error, shaped like the ones OpenAI returns.
+    pub fn error(status: u16, message: &str, r#type: &str, code: Option<&str>) -> Self {
+        Self::json(json!({
+            "error": {
+                "message": message,
+                "type": r#type,
+                "param": null,
+                "code": code,
+            }
+        }))
+        .status(status)
+    }
+
+    pub fn status(mut self, status: u16) -> Self {
+        self.status = status;
+
+        self
+    }
+
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+
+        self
+    }
+
+    fn into_response(self) -> Response {
+        let mut builder = http::Response::builder().status(self.status);
+        if !self
+            .headers
+            .iter()
+            .any(|(name, _)| name.eq_ignore_ascii_case("x-request-id"))
+        {
+            builder = builder.header("x-request-id", "req_mock");
+        }
+        for (name, value) in self.headers {
+            builder = builder.header(name, value);
+        }
+
+        let chunks = futures::stream::iter(self.chunks.into_iter().map(Ok));
+        builder
+            .body(Body::wrap_stream(chunks))
+            .expect("Err building mock response.")
+            .into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matches() {
+        assert!(matches("chat/completions", "/v1/chat/completions"));
+        assert!(!matches("completions", "/v1/chat/completions"));
+        assert!(matches("files/*", "/v1/files/file-123"));
+        assert!(!matches("files/*", "/v1/files"));
+        assert!(matches(
+            "/openai/deployments/*/embeddings",
+            "/openai/deployments/ada/embeddings"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_mock_order() {
+        let mock = MockTransport::new();
+        mock.once(Method::GET, "models", MockResponse::json(json!({"n": 1})))
+            .on(Method::GET, "models", MockResponse::json(json!({"n": 2})));
+        let client = mock.client();
+
+        for n in [1, 2, 2] {
+            let resp: Value = client.get::<(), _>("models", None).await.unwrap();
+            assert_eq!(resp["n"], n);
+        }
+
+        let err = client.get::<(), Value>("engines", None).await.unwrap_err();
+        assert_eq!(err.status(), Some(404));
+
+        let request = mock.assert_requested(Method::GET, "engines");
+        assert_eq!(
+            request.headers["authorization"].to_str().unwrap(),
+            "Bearer sk-test"
+        );
+        assert_eq!(mock.requests().len(), 4);
+    }
+}
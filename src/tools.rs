@@ -0,0 +1,260 @@
+//! Calling Rust functions from chat completions.
+//!
+//! A [`ToolRegistry`] pairs each [`Tool`] offered to the model with the async function answering its calls.
+//! [`run_with_tools`](crate::chat::run_with_tools) then drives the conversation: each time the model calls tools,
+//! the matching functions run concurrently, their results are appended to the messages, and the chat is sent again,
+//! until the model replies without calling any tool.
+//!
+//! Arguments that can't be deserialized into the function's parameter type, unknown tools and errors returned
+//! by the functions are reported back to the model, as the result of the call, so it can correct itself.
+//!
+//! ## Usage
+//! ```no_run
+//! use fieri::{
+//!     chat::run_with_tools,
+//!     tools::ToolRegistry,
+//!     types::{ChatMessageBuilder, ChatParamBuilder},
+//!     Client,
+//! };
+//! use serde::Deserialize;
+//! use serde_json::json;
+//!
+//! #[derive(Deserialize)]
+//! struct Location {
+//!     city: String,
+//! }
+//!
+//! #[tokio::main]
+//! async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//!     let client = Client::new();
+//!
+//!     let registry = ToolRegistry::new().register(
+//!         "get_weather",
+//!         "Get the current weather in a given city",
+//!         json!({
+//!             "type": "object",
+//!             "properties": {"city": {"type": "string"}},
+//!             "required": ["city"],
+//!         }),
+//!         |location: Location| async move {
+//!             Ok::<_, std::io::Error>(json!({"city": location.city, "temperature": 22}))
+//!         },
+//!     );
+//!
+//!     let message = ChatMessageBuilder::new("user", "What's the weather in Paris?").build()?;
+//!     let param = ChatParamBuilder::new("gpt-4o", vec![message]).build()?;
+//!
+//!     let run = run_with_tools(&client, &param, &registry, 5).await?;
+//!     println!("{}", run.chat.choices[0].message.content);
+//!
+//!     Ok(())
+//! }
+//! ```
+
+use std::{collections::HashMap, fmt, future::Future, sync::Arc};
+
+use futures::future::{self, BoxFuture};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+
+use crate::types::{Chat, ChatMessage, ChatRole, Tool, ToolCall};
+
+/// Answers a call with its arguments, or with the error to report to the model.
+type Handler = Arc<dyn Fn(&str) -> BoxFuture<'static, Result<String, String>> + Send + Sync>;
+
+/// The tools offered to the model, and the functions answering their calls.
+///
+/// Clones share the same functions.
+#[derive(Clone, Default)]
+pub struct ToolRegistry {
+    tools: Vec<Tool>,
+    handlers: HashMap<String, Handler>,
+}
+
+impl fmt::Debug for ToolRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ToolRegistry")
+            .field("tools", &self.tools)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ToolRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a function as a tool, with its arguments described by the given JSON Schema.
+    ///
+    /// The arguments generated by the model are deserialized into `A`, and the returned value is sent back
+    /// to the model, as-is for strings and JSON encoded otherwise.
+    /// Registering a name again replaces the previous tool.
+    pub fn register<A, R, E, F, Fut>(
+        mut self,
+        name: impl Into<String>,
+        description: impl Into<String>,
+        parameters: Value,
+        function: F,
+    ) -> Self
+    where
+        A: DeserializeOwned,
+        R: Serialize,
+        E: fmt::Display,
+        F: Fn(A) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<R, E>> + Send + 'static,
+    {
+        let tool = Tool::function(name, description, parameters);
+        let name = tool.function.name.clone();
+
+        let handler: Handler = Arc::new(move |arguments| {
+            let arguments = match serde_json::from_str::<A>(arguments) {
+                Ok(arguments) => arguments,
+                Err(err) => {
+                    let err = format!("Invalid arguments: {err}");
+                    return Box::pin(future::ready(Err(err)));
+                }
+            };
+
+            let call = function(arguments);
+            Box::pin(async move {
+                match serde_json::to_value(call.await.map_err(|e| format!("Error: {e}"))?) {
+                    Ok(Value::String(s)) => Ok(s),
+                    Ok(value) => Ok(value.to_string()),
+                    Err(err) => Err(format!("Error: {err}")),
+                }
+            })
+        });
+
+        self.tools.retain(|t| t.function.name != name);
+        self.tools.push(tool);
+        self.handlers.insert(name, handler);
+
+        self
+    }
+
+    /// The registered tools, in the order they were registered.
+    pub fn tools(&self) -> &[Tool] {
+        &self.tools
+    }
+
+    /// Runs the function of the given call, returning the tool message with its result.
+    pub async fn call(&self, call: &ToolCall) -> ChatMessage {
+        let result = match self.handlers.get(&call.function.name) {
+            Some(handler) => handler(&call.function.arguments).await,
+            None => Err(format!("Unknown tool: {}", call.function.name)),
+        };
+        if let Err(err) = &result {
+            log::debug!("Tool call {} failed: {err}", call.id);
+        }
+
+        ChatMessage {
+            role: ChatRole::Tool,
+            content: result.unwrap_or_else(|err| err).into(),
+            tool_call_id: Some(call.id.clone()),
+            ..ChatMessage::default()
+        }
+    }
+
+    /// Runs the functions of the given calls concurrently, returning their tool messages in the same order.
+    pub async fn call_all(&self, calls: &[ToolCall]) -> Vec<ChatMessage> {
+        future::join_all(calls.iter().map(|call| self.call(call))).await
+    }
+}
+
+/// The outcome of [`run_with_tools`](crate::chat::run_with_tools).
+#[derive(Clone, Debug, Default)]
+pub struct ToolRun {
+    /// The whole conversation: the given messages, then each reply of the model followed by the results of its calls.
+    pub messages: Vec<ChatMessage>,
+
+    /// The last response, which called no tool.
+    pub chat: Chat,
+
+    /// How many chats were sent.
+    pub iterations: usize,
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+    use serde_json::json;
+
+    use super::*;
+    use crate::types::FunctionCall;
+
+    #[derive(Deserialize)]
+    struct Sum {
+        a: i64,
+        b: i64,
+    }
+
+    fn registry() -> ToolRegistry {
+        ToolRegistry::new()
+            .register(
+                "sum",
+                "Adds two numbers",
+                json!({"type": "object"}),
+                |sum: Sum| async move { Ok::<_, String>(json!({"sum": sum.a + sum.b})) },
+            )
+            .register(
+                "fail",
+                "Always fails",
+                json!({"type": "object"}),
+                |_: Value| async move { Err::<(), _>("out of order") },
+            )
+    }
+
+    fn call(id: &str, name: &str, arguments: &str) -> ToolCall {
+        ToolCall {
+            id: id.to_string(),
+            function: FunctionCall {
+                name: name.to_string(),
+                arguments: arguments.to_string(),
+            },
+            ..ToolCall::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_registry_call_all() {
+        let results = registry()
+            .call_all(&[
+                call("call_1", "sum", r#"{"a": 1, "b": 2}"#),
+                call("call_2", "sum", r#"{"a": "one"}"#),
+                call("call_3", "fail", "{}"),
+                call("call_4", "missing", "{}"),
+            ])
+            .await;
+
+        assert!(results.iter().all(|m| m.role == ChatRole::Tool));
+        assert_eq!(results[0].tool_call_id.as_deref(), Some("call_1"));
+        assert_eq!(results[0].content, r#"{"sum":3}"#);
+        assert!(results[1]
+            .content
+            .to_text()
+            .starts_with("Invalid arguments: "));
+        assert_eq!(results[2].content, "Error: out of order");
+        assert_eq!(results[3].content, "Unknown tool: missing");
+    }
+
+    #[test]
+    fn test_registry_replaces_tools() {
+        let registry = registry().register(
+            "sum",
+            "Adds two integers",
+            json!({"type": "object"}),
+            |sum: Sum| async move { Ok::<_, String>(sum.a + sum.b) },
+        );
+
+        let names: Vec<_> = registry
+            .tools()
+            .iter()
+            .map(|t| t.function.name.as_str())
+            .collect();
+        assert_eq!(names, ["fail", "sum"]);
+        assert_eq!(
+            registry.tools()[1].function.description.as_deref(),
+            Some("Adds two integers")
+        );
+    }
+}
@@ -0,0 +1,432 @@
+//! Instrumentation of each request: a [`tracing`](https://docs.rs/tracing) span,
+//! named & attributed after the [OpenTelemetry GenAI semantic conventions](https://opentelemetry.io/docs/specs/semconv/gen-ai/),
+//! and the [`RequestMetrics`] reported to the [`MetricsSink`](crate::metrics::MetricsSink), if any.
+//!
+//! Spans are only recorded with the `tracing` feature.
+//! The content of prompts & completions is only recorded when enabled with `Client::trace_content`.
+
+use std::{
+    future::Future,
+    sync::{Arc, Mutex},
+    time::Instant,
+};
+
+use reqwest::header::HeaderMap;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use url::Url;
+
+use crate::{
+    metrics::{self, Report, RequestMetrics, Sink},
+    Result,
+};
+
+/// The instrumentation of a single call to an endpoint.
+///
+/// Metrics are reported once every clone, including the one held by a stream, has been dropped.
+#[derive(Clone, Debug)]
+pub(crate) struct Trace {
+    #[cfg(feature = "tracing")]
+    span: tracing::Span,
+
+    /// Whether to record the content of prompts & completions.
+    #[cfg(feature = "tracing")]
+    content: bool,
+
+    start: Instant,
+
+    /// Whether the first event of a stream has been received.
+    streaming: bool,
+
+    report: Option<Arc<Mutex<Report>>>,
+}
+
+/// The parts of a response worth recording, shared by every endpoint.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct Summary {
+    id: Option<String>,
+    model: Option<String>,
+    choices: Vec<Value>,
+    usage: Option<Value>,
+}
+
+#[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+impl Trace {
+    pub fn new<X: Serialize>(
+        method: &'static str,
+        identifier: &str,
+        url: &Url,
+        body: Option<&X>,
+        content: bool,
+        sink: Option<&Sink>,
+    ) -> Self {
+        #[cfg(feature = "tracing")]
+        let traced = tracing::enabled!(tracing::Level::INFO);
+        #[cfg(not(feature = "tracing"))]
+        let traced = false;
+
+        // Serializing the body again is only worth it when a span or a sink records its parameters.
+        let body = match traced || sink.is_some() {
+            true => body.and_then(|b| serde_json::to_value(b).ok()),
+            false => None,
+        };
+        let param = |name: &str| body.as_ref().and_then(|b| b.get(name)).cloned();
+        let model = param("model").and_then(|m| m.as_str().map(String::from));
+        let report = sink
+            .map(|sink| Report::new(sink, RequestMetrics::new(method, identifier, model.clone())));
+
+        #[cfg(feature = "tracing")]
+        {
+            use tracing::field::Empty;
+
+            let operation = operation(identifier);
+            let span = tracing::info_span!(
+                "gen_ai",
+                otel.name = %match &model {
+                    Some(model) => format!("{operation} {model}"),
+                    None => operation.to_string(),
+                },
+                otel.kind = "client",
+                otel.status_code = Empty,
+                gen_ai.system = "openai",
+                gen_ai.operation.name = operation,
+                gen_ai.request.model = model,
+                gen_ai.request.max_tokens = param("max_tokens").and_then(|v| v.as_u64()),
+                gen_ai.request.temperature = param("temperature").and_then(|v| v.as_f64()),
+                gen_ai.request.top_p = param("top_p").and_then(|v| v.as_f64()),
+                gen_ai.request.seed = param("seed").and_then(|v| v.as_u64()),
+                gen_ai.prompt = Empty,
+                gen_ai.response.id = Empty,
+                gen_ai.response.model = Empty,
+                gen_ai.response.finish_reasons = Empty,
+                gen_ai.completion = Empty,
+                gen_ai.usage.input_tokens = Empty,
+                gen_ai.usage.output_tokens = Empty,
+                gen_ai.server.time_to_first_token = Empty,
+                http.request.method = method,
+                http.request.resend_count = Empty,
+                http.response.status_code = Empty,
+                server.address = url.host_str(),
+                url.path = url.path(),
+                openai.request.id = Empty,
+                fieri.cache_hit = Empty,
+                error.type = Empty,
+            );
+
+            if content {
+                let prompt = ["messages", "prompt", "input"].into_iter().find_map(param);
+                if let Some(prompt) = prompt {
+                    span.record("gen_ai.prompt", prompt.to_string());
+                }
+            }
+
+            Self {
+                span,
+                content,
+                start: Instant::now(),
+                streaming: false,
+                report,
+            }
+        }
+
+        #[cfg(not(feature = "tracing"))]
+        Self {
+            start: Instant::now(),
+            streaming: false,
+            report,
+        }
+    }
+
+    /// Runs the given call within the span, recording its error, if any.
+    pub async fn run<T>(&self, call: impl Future<Output = Result<T>>) -> Result<T> {
+        #[cfg(feature = "tracing")]
+        let result = {
+            use tracing::Instrument;
+
+            call.instrument(self.span.clone()).await
+        };
+        #[cfg(not(feature = "tracing"))]
+        let result = call.await;
+
+        if let Err(err) = &result {
+            #[cfg(feature = "tracing")]
+            {
+                self.span.record("otel.status_code", "ERROR");
+                self.span.record(
+                    "error.type",
+                    err.status()
+                        .map(|s| s.to_string())
+                        .unwrap_or_else(|| "_OTHER".to_string()),
+                );
+            }
+
+            self.metrics(|m| m.error = Some(metrics::kind(err)));
+        }
+
+        result
+    }
+
+    /// Records the response of the given (1-based) attempt, before its body has been read.
+    pub fn response(&self, attempt: u32, status: u16, headers: &HeaderMap) {
+        #[cfg(feature = "tracing")]
+        {
+            if attempt > 1 {
+                self.span.record("http.request.resend_count", attempt - 1);
+            }
+            self.span.record("http.response.status_code", status);
+            if let Some(id) = headers.get("x-request-id").and_then(|v| v.to_str().ok()) {
+                self.span.record("openai.request.id", id);
+            }
+        }
+
+        self.metrics(|m| {
+            m.status = Some(status);
+            m.retries = attempt - 1;
+        });
+    }
+
+    pub fn cache_hit(&self) {
+        #[cfg(feature = "tracing")]
+        self.span.record("fieri.cache_hit", true);
+
+        self.metrics(|m| {
+            m.status = Some(200);
+            m.cached = true;
+        });
+    }
+
+    /// Records the body of a response.
+    pub fn body(&self, body: &[u8]) {
+        if !self.enabled() {
+            return;
+        }
+
+        if let Ok(summary) = serde_json::from_slice::<Summary>(body) {
+            self.summary(summary);
+        }
+    }
+
+    /// Records an event of a streamed response.
+    pub fn event(&mut self, data: &str) {
+        if !self.enabled() {
+            return;
+        }
+
+        if !self.streaming {
+            self.streaming = true;
+
+            let elapsed = self.start.elapsed();
+            #[cfg(feature = "tracing")]
+            self.span
+                .record("gen_ai.server.time_to_first_token", elapsed.as_secs_f64());
+            self.metrics(|m| m.time_to_first_token = Some(elapsed));
+        }
+
+        if let Ok(summary) = serde_json::from_str::<Summary>(data) {
+            self.summary(summary);
+        }
+    }
+
+    /// Whether anything is recorded at all.
+    fn enabled(&self) -> bool {
+        cfg!(feature = "tracing") || self.report.is_some()
+    }
+
+    fn metrics(&self, f: impl FnOnce(&mut RequestMetrics)) {
+        if let Some(report) = &self.report {
+            f(&mut report.lock().unwrap().metrics);
+        }
+    }
+
+    fn summary(&self, summary: Summary) {
+        let tokens = |name: &str| summary.usage.as_ref()?.get(name)?.as_u64();
+        let (input, output) = (tokens("prompt_tokens"), tokens("completion_tokens"));
+        if input.is_some() {
+            self.metrics(|m| {
+                m.prompt_tokens = input;
+                m.completion_tokens = output;
+            });
+        }
+
+        #[cfg(feature = "tracing")]
+        {
+            let span = &self.span;
+
+            if let Some(id) = &summary.id {
+                span.record("gen_ai.response.id", id.as_str());
+            }
+            if let Some(model) = &summary.model {
+                span.record("gen_ai.response.model", model.as_str());
+            }
+            if let Some(input) = input {
+                span.record("gen_ai.usage.input_tokens", input);
+            }
+            if let Some(output) = output {
+                span.record("gen_ai.usage.output_tokens", output);
+            }
+
+            let finish_reasons: Vec<&str> = summary
+                .choices
+                .iter()
+                .filter_map(|c| c.get("finish_reason")?.as_str())
+                .collect();
+            if !finish_reasons.is_empty() {
+                span.record(
+                    "gen_ai.response.finish_reasons",
+                    format!("{finish_reasons:?}"),
+                );
+            }
+
+            // Streamed completions arrive in pieces, so only whole ones are recorded.
+            if self.content && !self.streaming && !summary.choices.is_empty() {
+                span.record(
+                    "gen_ai.completion",
+                    Value::from(summary.choices).to_string(),
+                );
+            }
+        }
+    }
+}
+
+/// The GenAI operation performed by the given endpoint.
+#[cfg(feature = "tracing")]
+fn operation(identifier: &str) -> &str {
+    match identifier {
+        "chat/completions" => "chat",
+        "completions" => "text_completion",
+        "embeddings" => "embeddings",
+        _ => identifier.split('/').next().unwrap_or(identifier),
+    }
+}
+
+#[cfg(all(test, feature = "tracing"))]
+mod tests {
+    use std::{
+        collections::HashMap,
+        fmt,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc, Mutex,
+        },
+    };
+
+    use serde::{Serialize, Serializer};
+    use url::Url;
+
+    use tracing::{
+        field::{Field, Visit},
+        span::{Attributes, Id, Record},
+        Event, Metadata, Subscriber,
+    };
+
+    use super::Trace;
+    use crate::{
+        chat::chat,
+        testing::MockTransport,
+        types::{ChatMessageBuilder, ChatParamBuilder},
+    };
+
+    /// Collects the fields of every span.
+    #[derive(Clone, Default)]
+    struct Fields(Arc<Mutex<HashMap<String, String>>>);
+
+    impl Visit for Fields {
+        fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+            self.0
+                .lock()
+                .unwrap()
+                .insert(field.name().to_string(), format!("{value:?}"));
+        }
+
+        fn record_str(&mut self, field: &Field, value: &str) {
+            self.0
+                .lock()
+                .unwrap()
+                .insert(field.name().to_string(), value.to_string());
+        }
+    }
+
+    impl Subscriber for Fields {
+        fn enabled(&self, _: &Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, span: &Attributes<'_>) -> Id {
+            span.record(&mut self.clone());
+            Id::from_u64(1)
+        }
+
+        fn record(&self, _: &Id, values: &Record<'_>) {
+            values.record(&mut self.clone());
+        }
+
+        fn record_follows_from(&self, _: &Id, _: &Id) {}
+        fn event(&self, _: &Event<'_>) {}
+        fn enter(&self, _: &Id) {}
+        fn exit(&self, _: &Id) {}
+    }
+
+    #[test]
+    fn test_chat_span() {
+        let fields = Fields::default();
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+
+        tracing::subscriber::with_default(fields.clone(), || {
+            runtime.block_on(async {
+                let client = MockTransport::with_fixtures().client().trace_content(true);
+                let message = ChatMessageBuilder::new("user", "Hello!").build().unwrap();
+                let param = ChatParamBuilder::new("gpt-3.5-turbo", vec![message])
+                    .max_tokens(64_u32)
+                    .build()
+                    .unwrap();
+
+                chat(&client, &param).await.unwrap();
+            })
+        });
+
+        let fields = fields.0.lock().unwrap();
+        let field = |name: &str| fields.get(name).map(String::as_str);
+
+        assert_eq!(field("otel.name"), Some("chat gpt-3.5-turbo"));
+        assert_eq!(field("gen_ai.operation.name"), Some("chat"));
+        assert_eq!(field("gen_ai.request.max_tokens"), Some("64"));
+        assert_eq!(field("gen_ai.response.id"), Some("chatcmpl-abc123"));
+        assert_eq!(field("gen_ai.response.finish_reasons"), Some("[\"stop\"]"));
+        assert_eq!(field("gen_ai.usage.input_tokens"), Some("9"));
+        assert_eq!(field("http.response.status_code"), Some("200"));
+        assert_eq!(field("openai.request.id"), Some("req_mock"));
+        assert!(field("gen_ai.prompt").unwrap().contains("Hello!"));
+        assert!(field("gen_ai.completion")
+            .unwrap()
+            .contains("How can I help"));
+    }
+
+    #[test]
+    fn test_untraced_body_not_serialized() {
+        /// Counts how many times it's serialized.
+        struct Counted(Arc<AtomicUsize>);
+
+        impl Serialize for Counted {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                self.0.fetch_add(1, Ordering::SeqCst);
+                serializer.serialize_unit()
+            }
+        }
+
+        let count = Arc::new(AtomicUsize::new(0));
+        let url = Url::parse("https://api.openai.com/v1/chat/completions").unwrap();
+        let body = Counted(count.clone());
+
+        Trace::new("POST", "chat/completions", &url, Some(&body), false, None);
+        assert_eq!(count.load(Ordering::SeqCst), 0);
+
+        tracing::subscriber::with_default(Fields::default(), || {
+            Trace::new("POST", "chat/completions", &url, Some(&body), false, None);
+        });
+        assert_eq!(count.load(Ordering::SeqCst), 1);
+    }
+}
@@ -0,0 +1,406 @@
+//! The HTTP stack sending the requests of a [`Client`](crate::Client).
+//!
+//! By default, requests are sent with [`reqwest`], configured by the [`ClientBuilder`](crate::ClientBuilder).
+//! Any other [`Transport`] can be plugged in instead, like a fake one for tests, or an instrumented HTTP stack.
+//!
+//! Transports, like [`Middleware`](crate::middleware::Middleware), only deal with fieri's own [`Request`] & [`Response`],
+//! whose bodies are either whole, or a stream of bytes. [`reqwest`] is one adapter among others.
+//!
+//! ## Usage
+//! ```no_run
+//! use fieri::{
+//!     transport::{Request, Response, Transport},
+//!     Client, Result,
+//! };
+//! use futures::future::BoxFuture;
+//!
+//! struct Canned;
+//!
+//! impl Transport for Canned {
+//!     fn send(&self, request: Request) -> BoxFuture<'_, Result<Response>> {
+//!         Box::pin(async move {
+//!             println!("{} {}", request.method(), request.url());
+//!             Ok(Response::new(r#"{"object": "list", "data": []}"#))
+//!         })
+//!     }
+//! }
+//!
+//! let client = Client::new().transport(Canned);
+//! ```
+
+use std::{fmt, pin::Pin, sync::Arc, time::Duration};
+
+use bytes::Bytes;
+use futures::{future::BoxFuture, Stream, TryStreamExt};
+use http::{HeaderMap, Method, StatusCode};
+use url::Url;
+
+use crate::{Error, Result};
+
+/// Sends a request, and returns its response once the status & headers have arrived.
+///
+/// The body of the response may still be streaming in.
+pub trait Transport: Send + Sync + 'static {
+    fn send(&self, request: Request) -> BoxFuture<'_, Result<Response>>;
+}
+
+/// A stream of the bytes of a body.
+pub type ByteStream = Pin<Box<dyn Stream<Item = Result<Bytes>> + Send + Sync>>;
+
+/// The body of a [`Request`] or [`Response`], either whole, or streamed.
+pub enum Body {
+    Bytes(Bytes),
+    Stream(ByteStream),
+}
+
+impl Body {
+    pub fn empty() -> Self {
+        Self::Bytes(Bytes::new())
+    }
+
+    /// A body streaming the given chunks.
+    pub fn wrap_stream<S, B>(stream: S) -> Self
+    where
+        S: Stream<Item = Result<B>> + Send + Sync + 'static,
+        B: Into<Bytes> + 'static,
+    {
+        Self::Stream(Box::pin(stream.map_ok(Into::into)))
+    }
+
+    /// The whole body, unless it's streamed.
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match self {
+            Self::Bytes(bytes) => Some(bytes),
+            Self::Stream(_) => None,
+        }
+    }
+
+    /// Whether the body is known to be empty.
+    pub fn is_empty(&self) -> bool {
+        self.as_bytes().is_some_and(|b| b.is_empty())
+    }
+
+    /// Reads the whole body.
+    pub async fn bytes(self) -> Result<Bytes> {
+        match self {
+            Self::Bytes(bytes) => Ok(bytes),
+            Self::Stream(stream) => {
+                let chunks: Vec<Bytes> = stream.try_collect().await?;
+                Ok(chunks.concat().into())
+            }
+        }
+    }
+
+    /// The body as a stream of chunks, however it's held.
+    pub fn into_stream(self) -> ByteStream {
+        match self {
+            Self::Bytes(bytes) => Box::pin(futures::stream::once(async { Ok(bytes) })),
+            Self::Stream(stream) => stream,
+        }
+    }
+}
+
+impl Default for Body {
+    fn default() -> Self {
+        Self::empty()
+    }
+}
+
+impl fmt::Debug for Body {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Bytes(bytes) => f.debug_tuple("Bytes").field(&bytes.len()).finish(),
+            Self::Stream(_) => f.write_str("Stream"),
+        }
+    }
+}
+
+impl From<Bytes> for Body {
+    fn from(bytes: Bytes) -> Self {
+        Self::Bytes(bytes)
+    }
+}
+
+impl From<Vec<u8>> for Body {
+    fn from(bytes: Vec<u8>) -> Self {
+        Self::Bytes(bytes.into())
+    }
+}
+
+impl From<String> for Body {
+    fn from(s: String) -> Self {
+        Self::Bytes(s.into())
+    }
+}
+
+impl From<&'static str> for Body {
+    fn from(s: &'static str) -> Self {
+        Self::Bytes(s.into())
+    }
+}
+
+/// A request, about to be sent by a [`Transport`].
+#[derive(Debug)]
+pub struct Request {
+    method: Method,
+    url: Url,
+    headers: HeaderMap,
+    body: Body,
+    timeout: Option<Duration>,
+}
+
+impl Request {
+    /// A request without headers or a body.
+    pub fn new(method: Method, url: Url) -> Self {
+        Self {
+            method,
+            url,
+            headers: HeaderMap::new(),
+            body: Body::empty(),
+            timeout: None,
+        }
+    }
+
+    pub fn method(&self) -> &Method {
+        &self.method
+    }
+
+    pub fn method_mut(&mut self) -> &mut Method {
+        &mut self.method
+    }
+
+    pub fn url(&self) -> &Url {
+        &self.url
+    }
+
+    pub fn url_mut(&mut self) -> &mut Url {
+        &mut self.url
+    }
+
+    pub fn headers(&self) -> &HeaderMap {
+        &self.headers
+    }
+
+    pub fn headers_mut(&mut self) -> &mut HeaderMap {
+        &mut self.headers
+    }
+
+    pub fn body(&self) -> &Body {
+        &self.body
+    }
+
+    pub fn body_mut(&mut self) -> &mut Body {
+        &mut self.body
+    }
+
+    /// The timeout of this request, in place of the one of the transport.
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    pub fn timeout_mut(&mut self) -> &mut Option<Duration> {
+        &mut self.timeout
+    }
+}
+
+/// The response to a [`Request`], whose body may still be streaming in.
+#[derive(Debug)]
+pub struct Response {
+    status: StatusCode,
+    headers: HeaderMap,
+    body: Body,
+}
+
+impl Response {
+    /// A `200 OK` response, without headers.
+    pub fn new(body: impl Into<Body>) -> Self {
+        Self {
+            status: StatusCode::OK,
+            headers: HeaderMap::new(),
+            body: body.into(),
+        }
+    }
+
+    pub fn status(&self) -> StatusCode {
+        self.status
+    }
+
+    pub fn status_mut(&mut self) -> &mut StatusCode {
+        &mut self.status
+    }
+
+    pub fn headers(&self) -> &HeaderMap {
+        &self.headers
+    }
+
+    pub fn headers_mut(&mut self) -> &mut HeaderMap {
+        &mut self.headers
+    }
+
+    pub fn body_mut(&mut self) -> &mut Body {
+        &mut self.body
+    }
+
+    pub fn into_body(self) -> Body {
+        self.body
+    }
+
+    /// Reads the whole body.
+    pub async fn bytes(self) -> Result<Bytes> {
+        self.body.bytes().await
+    }
+
+    /// Reads the whole body, replacing invalid UTF-8 sequences.
+    pub async fn text(self) -> Result<String> {
+        Ok(String::from_utf8_lossy(&self.bytes().await?).into_owned())
+    }
+
+    /// The body, as a stream of chunks.
+    pub fn bytes_stream(self) -> ByteStream {
+        self.body.into_stream()
+    }
+}
+
+impl<T: Into<Body>> From<http::Response<T>> for Response {
+    fn from(resp: http::Response<T>) -> Self {
+        let (parts, body) = resp.into_parts();
+
+        Self {
+            status: parts.status,
+            headers: parts.headers,
+            body: body.into(),
+        }
+    }
+}
+
+/// The default transport, adapting requests & responses to [`reqwest`].
+impl Transport for reqwest::Client {
+    fn send(&self, request: Request) -> BoxFuture<'_, Result<Response>> {
+        Box::pin(async move {
+            let Request {
+                method,
+                url,
+                headers,
+                body,
+                timeout,
+            } = request;
+
+            let mut req = reqwest::Request::new(method, url);
+            *req.headers_mut() = headers;
+            *req.timeout_mut() = timeout;
+            *req.body_mut() = match body {
+                Body::Bytes(bytes) if bytes.is_empty() => None,
+                Body::Bytes(bytes) => Some(bytes.into()),
+                Body::Stream(stream) => Some(reqwest::Body::wrap_stream(stream)),
+            };
+
+            let mut resp = self.execute(req).await?;
+            let status = resp.status();
+            let headers = std::mem::take(resp.headers_mut());
+            let body = resp.bytes_stream().map_err(Error::from);
+
+            Ok(Response {
+                status,
+                headers,
+                body: Body::wrap_stream(body),
+            })
+        })
+    }
+}
+
+/// A transport plugged into a client, in place of its own [`reqwest::Client`].
+#[derive(Clone)]
+pub(crate) struct Custom(pub Arc<dyn Transport>);
+
+impl fmt::Debug for Custom {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Custom")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use futures::StreamExt;
+
+    use super::*;
+    use crate::Client;
+
+    /// Answers each request with the same body, keeping track of what was requested.
+    #[derive(Clone, Default)]
+    struct Fake {
+        body: &'static str,
+        requests: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl Transport for Fake {
+        fn send(&self, request: Request) -> BoxFuture<'_, Result<Response>> {
+            self.requests.lock().unwrap().push(format!(
+                "{} {}",
+                request.method(),
+                request.url().path()
+            ));
+
+            Box::pin(async move { Ok(Response::new(self.body)) })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_custom_transport() {
+        let fake = Fake {
+            body: r#"{"object": "list", "data": [{"id": "gpt-4"}]}"#,
+            ..Fake::default()
+        };
+        let client = Client::builder()
+            .api_key("sk-test")
+            .transport(fake.clone())
+            .build()
+            .unwrap();
+
+        let models: serde_json::Value = client.get::<(), _>("models", None).await.unwrap();
+
+        assert_eq!(models["data"][0]["id"], "gpt-4");
+        assert_eq!(*fake.requests.lock().unwrap(), vec!["GET /v1/models"]);
+    }
+
+    #[tokio::test]
+    async fn test_custom_transport_stream() {
+        let fake = Fake {
+            body: "data: {\"n\": 1}\n\ndata: {\"n\": 2}\n\ndata: [DONE]\n\n",
+            ..Fake::default()
+        };
+        let client = Client::new().api_key("sk-test").transport(fake);
+
+        let values: Vec<serde_json::Value> = client
+            .post_stream::<_, serde_json::Value>("completions", Some(&serde_json::json!({})))
+            .await
+            .unwrap()
+            .map(|v| v.unwrap())
+            .collect()
+            .await;
+
+        assert_eq!(
+            values,
+            vec![serde_json::json!({"n": 1}), serde_json::json!({"n": 2})]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_body() {
+        let chunks = futures::stream::iter(["data: ", "[DONE]"].map(Ok));
+        let body = Body::wrap_stream(chunks);
+        assert!(body.as_bytes().is_none());
+        assert_eq!(body.bytes().await.unwrap(), "data: [DONE]");
+
+        let resp: Response = http::Response::builder()
+            .status(404)
+            .header("x-request-id", "req_123")
+            .body("missing")
+            .unwrap()
+            .into();
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+        assert_eq!(resp.headers()["x-request-id"], "req_123");
+        assert_eq!(resp.text().await.unwrap(), "missing");
+    }
+}
@@ -1,7 +1,7 @@
 #![doc = include_str!("../../docs/types.md")]
 
 use std::{
-    borrow::Cow,
+    collections::{BTreeMap, HashMap},
     fmt::Display,
     fs,
     io::{copy, Cursor},
@@ -9,16 +9,19 @@ use std::{
     str::FromStr,
 };
 
-use clap::{builder, Parser};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use clap::Parser;
 use derive_builder::Builder;
-use reqwest::{
-    get,
-    multipart::{Form, Part},
-};
-use serde::{Deserialize, Serialize};
+use futures::{Stream, StreamExt};
+use reqwest::get;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{Map, Value};
 use serde_with::skip_serializing_none;
 
-use crate::{utils::is_false, Client, Result};
+use crate::{
+    utils::{is_false, lenient, null_as_default},
+    Result,
+};
 
 /// Tokens used for the requested action from OpenAI.
 #[derive(Clone, Debug, std::default::Default, serde::Deserialize, serde::Serialize)]
@@ -27,21 +30,61 @@ pub struct TokenUsage {
     pub prompt_tokens: u32,
     pub completion_tokens: u32,
     pub total_tokens: u32,
+
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 #[derive(Clone, Debug, std::default::Default, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
 pub struct Choices {
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(
+        default,
+        deserialize_with = "lenient",
+        skip_serializing_if = "Option::is_none"
+    )]
     pub text: Option<String>,
 
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(
+        default,
+        deserialize_with = "lenient",
+        skip_serializing_if = "Option::is_none"
+    )]
     pub index: Option<u32>,
 
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(
+        default,
+        deserialize_with = "lenient",
+        skip_serializing_if = "Option::is_none"
+    )]
     pub finish_reason: Option<String>,
 
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub logprobs: Option<f32>,
+    #[serde(
+        default,
+        deserialize_with = "lenient",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub logprobs: Option<Logprobs>,
+
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// The log probabilities of the tokens of a [`Choices`], when requested with `logprobs`.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Logprobs {
+    pub tokens: Vec<String>,
+
+    /// `None` for the first token of an echoed prompt, which has no log probability.
+    pub token_logprobs: Vec<Option<f32>>,
+
+    /// The most likely tokens at each position, with their log probabilities.
+    pub top_logprobs: Vec<Option<HashMap<String, f32>>>,
+    pub text_offset: Vec<u32>,
+
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 /// Information from requests wishing for a resource to be deleted, like [`Delete File`](crate::file::delete) and [`Delete Fine-tune`](crate::fine_tune::delete).
@@ -52,8 +95,15 @@ pub struct Delete {
     pub object: String,
     pub deleted: bool,
 
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(
+        default,
+        deserialize_with = "lenient",
+        skip_serializing_if = "Option::is_none"
+    )]
     pub token_usage: Option<TokenUsage>,
+
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 /// Response from endpoints like [`Upload File`](crate::file::upload), [`Retrieve file`][crate::file::retrieve] & [`Create Fine-tune`](crate::fine_tune::create).
@@ -68,8 +118,15 @@ pub struct File {
     pub purpose: String,
     pub status: String,
 
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(
+        default,
+        deserialize_with = "lenient",
+        skip_serializing_if = "Option::is_none"
+    )]
     pub token_usage: Option<TokenUsage>,
+
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 type Files = Vec<File>;
@@ -92,6 +149,16 @@ pub struct ChatParam {
     #[clap(long)]
     pub frequency_penalty: Option<f32>,
 
+    /// Whether to return the log probabilities of the generated tokens, in the [`ChatChoice::logprobs`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[clap(long)]
+    pub logprobs: Option<bool>,
+
+    /// How many of the most likely tokens to return at each position, between 0 and 20. Requires `logprobs`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[clap(long)]
+    pub top_logprobs: Option<u8>,
+
     /// The maximum number of tokens to generate in the chat completion.
     #[serde(skip_serializing_if = "Option::is_none")]
     #[clap(long)]
@@ -123,10 +190,15 @@ pub struct ChatParam {
     pub stop: Option<String>,
 
     /// If set, partial message deltas will be sent, like in ChatGPT.
-    #[serde(skip_serializing_if = "is_false")]
+    #[serde(default, skip_serializing_if = "is_false")]
     #[clap(long)]
     pub stream: bool,
 
+    /// Options for streaming responses. Only used together with `stream`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[clap(skip)]
+    pub stream_options: Option<StreamOptions>,
+
     /// What sampling temperature to use, between 0 and 2.
     /// Higher values like 0.8 will make the output more random,
     /// while lower values like 0.2 will make it more focused and deterministic.
@@ -141,47 +213,371 @@ pub struct ChatParam {
     #[clap(long)]
     pub top_p: Option<f32>,
 
+    /// The tools the model may call. Currently, only functions are supported as tools.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[clap(skip)]
+    pub tools: Option<Vec<Tool>>,
+
+    /// Controls which, if any, of the `tools` is called by the model.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[clap(skip)]
+    pub tool_choice: Option<ToolChoice>,
+
+    /// Whether the model may call multiple tools in a single response.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[clap(skip)]
+    pub parallel_tool_calls: Option<bool>,
+
+    /// The format the model must reply in, like JSON matching a schema.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[clap(skip)]
+    pub response_format: Option<ResponseFormat>,
+
     /// A unique identifier representing your end-user.
     #[serde(skip_serializing_if = "Option::is_none")]
     #[clap(long)]
     pub user: Option<String>,
 }
 
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct StreamOptions {
+    /// If set, an additional chunk with the [`TokenUsage`] of the whole request is streamed before the end of the stream.
+    pub include_usage: bool,
+}
+
+/// The type of a [`Tool`]. Currently, only functions are supported.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ToolType {
+    #[default]
+    Function,
+}
+
+/// A tool the model may call, listed in [`ChatParam::tools`].
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct Tool {
+    #[serde(rename = "type")]
+    pub kind: ToolType,
+    pub function: FunctionDefinition,
+}
+
+impl Tool {
+    /// A function tool, with its arguments described by the given JSON Schema.
+    pub fn function(
+        name: impl Into<String>,
+        description: impl Into<String>,
+        parameters: Value,
+    ) -> Self {
+        Self {
+            kind: ToolType::Function,
+            function: FunctionDefinition {
+                name: name.into(),
+                description: Some(description.into()),
+                parameters: Some(parameters),
+                strict: None,
+            },
+        }
+    }
+}
+
+#[skip_serializing_none]
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct FunctionDefinition {
+    /// The name of the function. May contain a-z, A-Z, 0-9, underscores and dashes, with a maximum length of 64 characters.
+    pub name: String,
+
+    /// What the function does, used by the model to choose when and how to call it.
+    pub description: Option<String>,
+
+    /// The arguments the function accepts, described as a JSON Schema object.
+    pub parameters: Option<Value>,
+
+    /// Whether the model must follow the exact schema of `parameters` when generating the arguments.
+    pub strict: Option<bool>,
+}
+
+/// Controls which, if any, tool is called by the model.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum ToolChoice {
+    /// The model won't call any tool, and generates a message instead.
+    None,
+
+    /// The model picks between generating a message or calling tools.
+    #[default]
+    Auto,
+
+    /// The model must call one or more tools.
+    Required,
+
+    /// The model must call the function with the given name.
+    Function(String),
+}
+
+impl Serialize for ToolChoice {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        match self {
+            ToolChoice::None => serializer.serialize_str("none"),
+            ToolChoice::Auto => serializer.serialize_str("auto"),
+            ToolChoice::Required => serializer.serialize_str("required"),
+            ToolChoice::Function(name) => serde_json::json!({
+                "type": "function",
+                "function": { "name": name },
+            })
+            .serialize(serializer),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for ToolChoice {
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<Self, D::Error> {
+        let value = Value::deserialize(deserializer)?;
+        match value.as_str() {
+            Some("none") => return Ok(Self::None),
+            Some("auto") => return Ok(Self::Auto),
+            Some("required") => return Ok(Self::Required),
+            _ => {}
+        }
+
+        value["function"]["name"]
+            .as_str()
+            .map(|name| Self::Function(name.to_string()))
+            .ok_or_else(|| serde::de::Error::custom(format!("invalid tool choice: {value}")))
+    }
+}
+
+/// A call to a tool, generated by the model.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ToolCall {
+    /// The ID of the call, referenced by the [`ChatMessage::tool_call_id`] of its result.
+    pub id: String,
+
+    #[serde(rename = "type")]
+    pub kind: ToolType,
+    pub function: FunctionCall,
+}
+
+/// The function called by a [`ToolCall`].
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct FunctionCall {
+    pub name: String,
+
+    /// The arguments of the call, as a JSON encoded string.
+    ///
+    /// The model doesn't always generate valid JSON, or respect the schema of the function.
+    pub arguments: String,
+}
+
+impl FunctionCall {
+    /// Deserializes the arguments of the call.
+    pub fn arguments<T: DeserializeOwned>(&self) -> serde_json::Result<T> {
+        serde_json::from_str(&self.arguments)
+    }
+}
+
+/// The part of a [`ToolCall`] generated since the previous chunk.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ToolCallDelta {
+    /// The position of the call in the [`ChatMessage::tool_calls`] of the choice.
+    pub index: u32,
+
+    /// Only sent with the first chunk of each call.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub kind: Option<ToolType>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub function: Option<FunctionCallDelta>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct FunctionCallDelta {
+    /// Only sent with the first chunk of each call.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub arguments: Option<String>,
+}
+
+/// The format the model must reply in, set in [`ChatParam::response_format`].
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ResponseFormat {
+    #[default]
+    Text,
+
+    /// Any valid JSON object. The messages must also ask the model for JSON.
+    JsonObject,
+
+    /// JSON matching the given schema.
+    JsonSchema { json_schema: JsonSchemaFormat },
+}
+
+impl ResponseFormat {
+    /// JSON matching the given schema, strictly followed by the model.
+    pub fn json_schema(name: impl Into<String>, schema: Value) -> Self {
+        Self::JsonSchema {
+            json_schema: JsonSchemaFormat {
+                name: name.into(),
+                description: None,
+                schema,
+                strict: Some(true),
+            },
+        }
+    }
+
+    /// JSON matching the schema derived from `T`, strictly followed by the model.
+    ///
+    /// Strict mode only supports a subset of JSON Schema, so every property is made required,
+    /// additional properties are forbidden, `oneOf` becomes `anyOf`, and unsupported formats are dropped.
+    #[cfg(feature = "schema")]
+    pub fn json_schema_for<T: schemars::JsonSchema>() -> Self {
+        let settings = schemars::gen::SchemaSettings::draft07().with(|s| {
+            s.meta_schema = None;
+        });
+        let schema = settings.into_generator().into_root_schema_for::<T>();
+        let mut schema = serde_json::to_value(schema).unwrap_or_default();
+        strict_schema(&mut schema);
+
+        let name: String = T::schema_name()
+            .chars()
+            .map(|c| match c.is_ascii_alphanumeric() || c == '-' {
+                true => c,
+                false => '_',
+            })
+            .take(64)
+            .collect();
+
+        Self::json_schema(name, schema)
+    }
+}
+
+/// Adapts a generated schema to the subset supported by strict mode.
+#[cfg(feature = "schema")]
+fn strict_schema(schema: &mut Value) {
+    match schema {
+        Value::Object(object) => {
+            if let Some(Value::Object(properties)) = object.get("properties") {
+                let required = properties.keys().cloned().map(Value::String).collect();
+                object.insert("required".to_string(), Value::Array(required));
+                object.insert("additionalProperties".to_string(), Value::Bool(false));
+            }
+            if matches!(object.get("format"), Some(Value::String(f)) if !STRICT_FORMATS.contains(&f.as_str()))
+            {
+                object.remove("format");
+            }
+            if let Some(one_of) = object.remove("oneOf") {
+                object.insert("anyOf".to_string(), one_of);
+            }
+
+            object.values_mut().for_each(strict_schema);
+        }
+        Value::Array(items) => items.iter_mut().for_each(strict_schema),
+        _ => {}
+    }
+}
+
+/// The string formats supported by strict mode.
+#[cfg(feature = "schema")]
+const STRICT_FORMATS: &[&str] = &[
+    "date-time",
+    "time",
+    "date",
+    "duration",
+    "email",
+    "hostname",
+    "ipv4",
+    "ipv6",
+    "uuid",
+];
+
+#[skip_serializing_none]
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct JsonSchemaFormat {
+    /// The name of the format. May contain a-z, A-Z, 0-9, underscores and dashes, with a maximum length of 64 characters.
+    pub name: String,
+
+    /// What the format is for, used by the model to decide how to reply in it.
+    pub description: Option<String>,
+
+    pub schema: Value,
+
+    /// Whether the model must follow the exact schema.
+    pub strict: Option<bool>,
+}
+
 #[skip_serializing_none]
 #[derive(Builder, Clone, Debug, Default, Deserialize, Serialize)]
 #[builder(default, setter(into, strip_option))]
 pub struct ChatMessage {
-    /// The role of the author of this message. One of system, user, or assistant.
+    /// The role of the author of this message. One of system, user, assistant, or tool.
     pub role: ChatRole,
 
-    /// The contents of the message.
-    pub content: String,
+    /// The contents of the message, either text or, for user messages, [`ContentPart`]s like images.
+    ///
+    /// Empty for assistant messages that only call tools.
+    /// Parts of types fieri doesn't know are kept as [`ContentPart::Unknown`].
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub content: ChatContent,
 
     /// The name of the author of this message. May contain a-z, A-Z, 0-9, and underscores, with a maximum length of 64 characters.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub name: Option<String>,
+
+    /// The tools called by the model, in assistant messages.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tool_calls: Option<Vec<ToolCall>>,
+
+    /// The ID of the [`ToolCall`] this message is the result of, in tool messages.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tool_call_id: Option<String>,
+
+    /// Why the model refused to reply, in place of the content of assistant messages.
+    #[serde(
+        default,
+        deserialize_with = "lenient",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub refusal: Option<String>,
+
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
 pub enum ChatRole {
     System,
+    #[default]
     User,
     Assistant,
+    Tool,
     Function,
 }
 
-impl Default for ChatRole {
-    fn default() -> Self {
-        Self::User
+impl From<String> for ChatRole {
+    fn from(s: String) -> Self {
+        Self::from(s.as_str())
     }
 }
 
-impl From<String> for ChatRole {
-    fn from(s: String) -> Self {
-        match s.as_str() {
+impl From<&str> for ChatRole {
+    fn from(s: &str) -> Self {
+        match s {
             "system" => Self::System,
             "user" => Self::User,
             "assistant" => Self::Assistant,
+            "tool" => Self::Tool,
             "function" => Self::Function,
             _ => Self::User,
         }
@@ -194,75 +590,732 @@ impl Display for ChatRole {
             ChatRole::System => "system",
             ChatRole::User => "user",
             ChatRole::Assistant => "assistant",
+            ChatRole::Tool => "tool",
             ChatRole::Function => "function",
         };
         write!(f, "{}", s)
     }
 }
 
-impl Serialize for ChatRole {
-    fn serialize<S: serde::Serializer>(
-        &self,
-        serializer: S,
-    ) -> std::result::Result<S::Ok, S::Error> {
-        serializer.serialize_str(self.to_string().as_str())
+impl Serialize for ChatRole {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.to_string().as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for ChatRole {
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        Ok(Self::from(s))
+    }
+}
+
+impl ChatMessageBuilder {
+    pub fn new(role: impl Into<ChatRole>, content: impl Into<ChatContent>) -> Self {
+        Self {
+            role: Some(role.into()),
+            content: Some(content.into()),
+            ..Self::default()
+        }
+    }
+}
+
+impl From<String> for ChatMessage {
+    fn from(s: String) -> Self {
+        Self {
+            role: ChatRole::default(),
+            content: s.into(),
+            name: None,
+            tool_calls: None,
+            tool_call_id: None,
+            refusal: None,
+            extra: Map::new(),
+        }
+    }
+}
+
+/// The contents of a [`ChatMessage`].
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum ChatContent {
+    Text(String),
+    Parts(Vec<ContentPart>),
+}
+
+impl ChatContent {
+    /// The text of the content, or `None` if it's made of parts.
+    pub fn as_text(&self) -> Option<&str> {
+        match self {
+            ChatContent::Text(text) => Some(text),
+            ChatContent::Parts(_) => None,
+        }
+    }
+
+    /// The text of the content, joining the text parts together if it's made of parts.
+    pub fn to_text(&self) -> String {
+        match self {
+            ChatContent::Text(text) => text.clone(),
+            ChatContent::Parts(parts) => parts
+                .iter()
+                .filter_map(|p| match p {
+                    ContentPart::Text { text } => Some(text.as_str()),
+                    _ => None,
+                })
+                .collect(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        match self {
+            ChatContent::Text(text) => text.is_empty(),
+            ChatContent::Parts(parts) => parts.is_empty(),
+        }
+    }
+
+    /// Appends text to the content, as a new text part if it's made of parts.
+    pub fn push_str(&mut self, s: &str) {
+        match self {
+            ChatContent::Text(text) => text.push_str(s),
+            ChatContent::Parts(parts) => parts.push(ContentPart::text(s)),
+        }
+    }
+}
+
+impl Default for ChatContent {
+    fn default() -> Self {
+        Self::Text(String::new())
+    }
+}
+
+impl Display for ChatContent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_text())
+    }
+}
+
+impl From<String> for ChatContent {
+    fn from(s: String) -> Self {
+        Self::Text(s)
+    }
+}
+
+impl From<&str> for ChatContent {
+    fn from(s: &str) -> Self {
+        Self::Text(s.to_string())
+    }
+}
+
+impl From<Vec<ContentPart>> for ChatContent {
+    fn from(parts: Vec<ContentPart>) -> Self {
+        Self::Parts(parts)
+    }
+}
+
+impl PartialEq<str> for ChatContent {
+    fn eq(&self, other: &str) -> bool {
+        self.as_text() == Some(other)
+    }
+}
+
+impl PartialEq<&str> for ChatContent {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_text() == Some(*other)
+    }
+}
+
+/// A part of the contents of a [`ChatMessage`].
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ContentPart {
+    Text {
+        text: String,
+    },
+    ImageUrl {
+        image_url: ImageUrl,
+    },
+    InputAudio {
+        input_audio: InputAudio,
+    },
+
+    /// A part of a type fieri doesn't model yet, like `file`, kept as is so it's sent back unchanged.
+    #[serde(untagged)]
+    Unknown(Value),
+}
+
+impl ContentPart {
+    pub fn text(text: impl Into<String>) -> Self {
+        Self::Text { text: text.into() }
+    }
+
+    /// An image, from the given URL, which can be a [`data:` URL](ImageUrl::from_path).
+    pub fn image_url(url: impl Into<String>) -> Self {
+        Self::ImageUrl {
+            image_url: ImageUrl {
+                url: url.into(),
+                detail: None,
+            },
+        }
+    }
+}
+
+impl From<ImageUrl> for ContentPart {
+    fn from(image_url: ImageUrl) -> Self {
+        Self::ImageUrl { image_url }
+    }
+}
+
+impl From<InputAudio> for ContentPart {
+    fn from(input_audio: InputAudio) -> Self {
+        Self::InputAudio { input_audio }
+    }
+}
+
+/// The largest image accepted by OpenAI, in bytes.
+pub const MAX_IMAGE_SIZE: u64 = 20 * 1024 * 1024;
+
+/// The largest audio input accepted by OpenAI, in bytes.
+pub const MAX_AUDIO_SIZE: u64 = 25 * 1024 * 1024;
+
+/// An image sent to vision models.
+#[skip_serializing_none]
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ImageUrl {
+    /// Either the URL of the image, or its base64 encoded data, as a `data:` URL.
+    pub url: String,
+
+    /// How closely the model looks at the image.
+    pub detail: Option<ImageDetail>,
+}
+
+impl ImageUrl {
+    /// Reads a PNG, JPEG, GIF or WEBP image into a `data:` URL.
+    ///
+    /// Images larger than [`MAX_IMAGE_SIZE`] are rejected before being read.
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self> {
+        Self::from_bytes(read_media(path.as_ref(), MAX_IMAGE_SIZE)?)
+    }
+
+    /// Encodes a PNG, JPEG, GIF or WEBP image into a `data:` URL, detecting its MIME type from its contents.
+    pub fn from_bytes(bytes: impl AsRef<[u8]>) -> Result<Self> {
+        let bytes = bytes.as_ref();
+        check_size(bytes.len() as u64, MAX_IMAGE_SIZE)?;
+
+        let mime = match bytes {
+            [0x89, b'P', b'N', b'G', ..] => "image/png",
+            [0xFF, 0xD8, 0xFF, ..] => "image/jpeg",
+            [b'G', b'I', b'F', b'8', ..] => "image/gif",
+            [b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'E', b'B', b'P', ..] => "image/webp",
+            _ => {
+                return Err(crate::Error::UnsupportedMedia(
+                    "Only PNG, JPEG, GIF and WEBP images are supported.".to_string(),
+                ))
+            }
+        };
+
+        Ok(Self {
+            url: format!("data:{mime};base64,{}", BASE64.encode(bytes)),
+            detail: None,
+        })
+    }
+
+    pub fn detail(mut self, detail: ImageDetail) -> Self {
+        self.detail = Some(detail);
+
+        self
+    }
+}
+
+/// How closely the model looks at an image, trading accuracy for speed & tokens.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImageDetail {
+    #[default]
+    Auto,
+    Low,
+    High,
+}
+
+/// Audio sent to audio models.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct InputAudio {
+    /// The base64 encoded audio.
+    pub data: String,
+    pub format: AudioFormat,
+}
+
+impl InputAudio {
+    /// Reads a WAV or MP3 file.
+    ///
+    /// Files larger than [`MAX_AUDIO_SIZE`] are rejected before being read.
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self> {
+        Self::from_bytes(read_media(path.as_ref(), MAX_AUDIO_SIZE)?)
+    }
+
+    /// Encodes WAV or MP3 audio, detecting its format from its contents.
+    pub fn from_bytes(bytes: impl AsRef<[u8]>) -> Result<Self> {
+        let bytes = bytes.as_ref();
+        check_size(bytes.len() as u64, MAX_AUDIO_SIZE)?;
+
+        let format = match bytes {
+            [b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'A', b'V', b'E', ..] => AudioFormat::Wav,
+            [b'I', b'D', b'3', ..] | [0xFF, 0xE0..=0xFF, ..] => AudioFormat::Mp3,
+            _ => {
+                return Err(crate::Error::UnsupportedMedia(
+                    "Only WAV and MP3 audio is supported.".to_string(),
+                ))
+            }
+        };
+
+        Ok(Self {
+            data: BASE64.encode(bytes),
+            format,
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AudioFormat {
+    #[default]
+    Wav,
+    Mp3,
+}
+
+/// Reads a file, unless it's larger than `max` bytes.
+fn read_media(path: &Path, max: u64) -> Result<Vec<u8>> {
+    check_size(fs::metadata(path)?.len(), max)?;
+
+    Ok(fs::read(path)?)
+}
+
+fn check_size(size: u64, max: u64) -> Result<()> {
+    match size > max {
+        true => Err(crate::Error::MediaTooLarge { size, max }),
+        false => Ok(()),
+    }
+}
+
+impl ChatParamBuilder {
+    pub fn new(model: impl Into<String>, messages: Vec<ChatMessage>) -> Self {
+        Self {
+            model: Some(model.into()),
+            messages: Some(messages),
+            ..Self::default()
+        }
+    }
+}
+
+#[derive(Builder, Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ChatChoice {
+    pub index: u32,
+    pub message: ChatMessage,
+
+    /// Why the model stopped generating, like `stop`, `length`, `tool_calls` or `content_filter`.
+    #[serde(
+        default,
+        deserialize_with = "lenient",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub finish_reason: Option<String>,
+
+    /// The log probabilities of the generated tokens, when requested with [`ChatParam::logprobs`].
+    #[serde(
+        default,
+        deserialize_with = "lenient",
+        skip_serializing_if = "Option::is_none"
+    )]
+    #[builder(default)]
+    pub logprobs: Option<ChatLogprobs>,
+
+    #[serde(flatten)]
+    #[builder(default)]
+    pub extra: Map<String, Value>,
+}
+
+/// The log probabilities of the tokens of a [`ChatChoice`].
+#[skip_serializing_none]
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ChatLogprobs {
+    pub content: Option<Vec<TokenLogprob>>,
+    pub refusal: Option<Vec<TokenLogprob>>,
+
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[skip_serializing_none]
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct TokenLogprob {
+    pub token: String,
+    pub logprob: f64,
+
+    /// The UTF-8 bytes of the token, useful when characters span multiple tokens.
+    pub bytes: Option<Vec<u8>>,
+
+    /// The most likely tokens at this position, when requested with [`ChatParam::top_logprobs`].
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub top_logprobs: Vec<TopLogprob>,
+
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[skip_serializing_none]
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct TopLogprob {
+    pub token: String,
+    pub logprob: f64,
+    pub bytes: Option<Vec<u8>>,
+
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// Response from [`Create Chat Completion`](crate::chat::chat).
+#[derive(Builder, Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Chat {
+    pub id: String,
+    pub object: String,
+    pub created: i64,
+
+    /// The model that generated the completion.
+    #[builder(default)]
+    pub model: String,
+    pub choices: Vec<ChatChoice>,
+
+    #[serde(deserialize_with = "lenient")]
+    pub usage: TokenUsage,
+
+    /// The configuration of the backend that generated the completion, changing with the results of a `seed`.
+    #[serde(
+        default,
+        deserialize_with = "lenient",
+        skip_serializing_if = "Option::is_none"
+    )]
+    #[builder(default)]
+    pub system_fingerprint: Option<String>,
+
+    /// The tier of service that processed the request, like `default` or `scale`.
+    #[serde(
+        default,
+        deserialize_with = "lenient",
+        skip_serializing_if = "Option::is_none"
+    )]
+    #[builder(default)]
+    pub service_tier: Option<String>,
+
+    #[serde(flatten)]
+    #[builder(default)]
+    pub extra: Map<String, Value>,
+}
+
+/// A partial [`Chat`], streamed by [`Chat Stream`](crate::chat::chat_stream).
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ChatChunk {
+    pub id: String,
+    pub object: String,
+    pub created: i64,
+    pub model: String,
+    pub choices: Vec<ChatChunkChoice>,
+
+    #[serde(
+        default,
+        deserialize_with = "lenient",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub usage: Option<TokenUsage>,
+
+    #[serde(
+        default,
+        deserialize_with = "lenient",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub system_fingerprint: Option<String>,
+
+    #[serde(
+        default,
+        deserialize_with = "lenient",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub service_tier: Option<String>,
+
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ChatChunkChoice {
+    pub index: u32,
+    pub delta: ChatDelta,
+
+    #[serde(
+        default,
+        deserialize_with = "lenient",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub finish_reason: Option<String>,
+
+    #[serde(
+        default,
+        deserialize_with = "lenient",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub logprobs: Option<ChatLogprobs>,
+
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// The part of the message generated since the previous chunk.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ChatDelta {
+    /// Only sent with the first chunk of each choice.
+    #[serde(
+        default,
+        deserialize_with = "lenient",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub role: Option<ChatRole>,
+
+    #[serde(
+        default,
+        deserialize_with = "lenient",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub content: Option<String>,
+
+    #[serde(
+        default,
+        deserialize_with = "lenient",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub tool_calls: Option<Vec<ToolCallDelta>>,
+
+    #[serde(
+        default,
+        deserialize_with = "lenient",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub refusal: Option<String>,
+
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// Rebuilds a complete [`Chat`] out of the chunks streamed by [`Chat Stream`](crate::chat::chat_stream),
+/// so the result is the same as if the request wasn't streamed.
+///
+/// ## Example
+/// ```no_run
+/// use futures::StreamExt;
+/// use fieri::{
+///     chat::chat_stream,
+///     types::{ChatMessageBuilder, ChatParamBuilder, ChatStreamAccumulator},
+///     Client,
+/// };
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let client = Client::new();
+///
+///     let message = ChatMessageBuilder::new("user", "Hello!").build()?;
+///     let param = ChatParamBuilder::new("gpt-3.5-turbo", vec![message]).build()?;
+///
+///     let mut stream = chat_stream(&client, &param).await?;
+///     let mut acc = ChatStreamAccumulator::new();
+///
+///     while let Some(chunk) = stream.next().await {
+///         let chunk = chunk?;
+///         for choice in &chunk.choices {
+///             print!("{}", choice.delta.content.as_deref().unwrap_or_default());
+///         }
+///         acc.push(&chunk);
+///     }
+///
+///     let chat = acc.finish();
+///     println!("{:#?}", chat);
+///
+///     Ok(())
+/// }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct ChatStreamAccumulator {
+    chat: Chat,
+
+    /// The tool calls of each choice, by their index, whichever order their chunks arrive in.
+    tool_calls: BTreeMap<u32, BTreeMap<u32, ToolCall>>,
+}
+
+impl ChatStreamAccumulator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Merges a chunk into the chat built so far.
+    pub fn push(&mut self, chunk: &ChatChunk) {
+        if self.chat.id.is_empty() {
+            self.chat.id = chunk.id.clone();
+            self.chat.object = "chat.completion".to_string();
+            self.chat.created = chunk.created;
+            self.chat.model = chunk.model.clone();
+        }
+        if chunk.system_fingerprint.is_some() {
+            self.chat.system_fingerprint = chunk.system_fingerprint.clone();
+        }
+        if chunk.service_tier.is_some() {
+            self.chat.service_tier = chunk.service_tier.clone();
+        }
+
+        for delta in &chunk.choices {
+            let choice = match self
+                .chat
+                .choices
+                .iter_mut()
+                .position(|c| c.index == delta.index)
+            {
+                Some(i) => &mut self.chat.choices[i],
+                None => {
+                    self.chat.choices.push(ChatChoice {
+                        index: delta.index,
+                        message: ChatMessage {
+                            role: ChatRole::Assistant,
+                            ..ChatMessage::default()
+                        },
+                        ..ChatChoice::default()
+                    });
+                    self.chat.choices.last_mut().unwrap()
+                }
+            };
+
+            if let Some(role) = delta.delta.role {
+                choice.message.role = role;
+            }
+            if let Some(content) = &delta.delta.content {
+                choice.message.content.push_str(content);
+            }
+            if let Some(refusal) = &delta.delta.refusal {
+                choice
+                    .message
+                    .refusal
+                    .get_or_insert_with(String::new)
+                    .push_str(refusal);
+            }
+            if let Some(logprobs) = &delta.logprobs {
+                let acc = choice.logprobs.get_or_insert_with(ChatLogprobs::default);
+                for (acc, tokens) in [
+                    (&mut acc.content, &logprobs.content),
+                    (&mut acc.refusal, &logprobs.refusal),
+                ] {
+                    if let Some(tokens) = tokens {
+                        acc.get_or_insert_with(Vec::new).extend_from_slice(tokens);
+                    }
+                }
+            }
+            for call in delta.delta.tool_calls.iter().flatten() {
+                let tool_call = self
+                    .tool_calls
+                    .entry(delta.index)
+                    .or_default()
+                    .entry(call.index)
+                    .or_default();
+                if let Some(id) = &call.id {
+                    tool_call.id = id.clone();
+                }
+                if let Some(kind) = call.kind {
+                    tool_call.kind = kind;
+                }
+                if let Some(function) = &call.function {
+                    if let Some(name) = &function.name {
+                        tool_call.function.name.push_str(name);
+                    }
+                    if let Some(arguments) = &function.arguments {
+                        tool_call.function.arguments.push_str(arguments);
+                    }
+                }
+            }
+            if delta.finish_reason.is_some() {
+                choice.finish_reason = delta.finish_reason.clone();
+            }
+        }
+
+        if let Some(usage) = &chunk.usage {
+            self.chat.usage = usage.clone();
+        }
     }
-}
 
-impl<'de> Deserialize<'de> for ChatRole {
-    fn deserialize<D: serde::Deserializer<'de>>(
-        deserializer: D,
-    ) -> std::result::Result<Self, D::Error> {
-        let s = String::deserialize(deserializer)?;
-        Ok(Self::from(s))
+    /// Returns the accumulated chat, with its choices, and their tool calls, ordered by index.
+    ///
+    /// Gaps between the indexes of tool calls are dropped.
+    pub fn finish(mut self) -> Chat {
+        for choice in &mut self.chat.choices {
+            if let Some(calls) = self.tool_calls.remove(&choice.index) {
+                choice.message.tool_calls = Some(calls.into_values().collect());
+            }
+        }
+
+        self.chat.choices.sort_by_key(|c| c.index);
+        self.chat
     }
-}
 
-impl ChatMessageBuilder {
-    pub fn new(role: impl Into<ChatRole>, content: impl Into<String>) -> Self {
-        Self {
-            role: Some(role.into()),
-            content: Some(content.into()),
-            ..Self::default()
+    /// Drains the whole stream, returning the accumulated chat.
+    pub async fn collect<S>(stream: S) -> Result<Chat>
+    where
+        S: Stream<Item = Result<ChatChunk>>,
+    {
+        futures::pin_mut!(stream);
+
+        let mut acc = Self::new();
+        while let Some(chunk) = stream.next().await {
+            acc.push(&chunk?);
         }
+
+        Ok(acc.finish())
     }
 }
 
-impl From<String> for ChatMessage {
-    fn from(s: String) -> Self {
-        Self {
-            role: ChatRole::default(),
-            content: s,
-            name: Some("rand".to_string()),
-        }
-    }
+/// One or more prompts to generate completions for, serialized as a string or an array of strings.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum Prompt {
+    Single(String),
+    Multiple(Vec<String>),
 }
 
-impl ChatParamBuilder {
-    pub fn new(model: impl Into<String>, messages: Vec<ChatMessage>) -> Self {
-        Self {
-            model: Some(model.into()),
-            messages: Some(messages),
-            ..Self::default()
-        }
+impl From<String> for Prompt {
+    fn from(prompt: String) -> Self {
+        Self::Single(prompt)
     }
 }
 
-#[derive(Builder, Clone, Debug, Default, Deserialize, Serialize)]
-pub struct ChatChoice {
-    pub index: u32,
-    pub message: ChatMessage,
-    pub finish_reason: Option<String>,
+impl From<&str> for Prompt {
+    fn from(prompt: &str) -> Self {
+        Self::Single(prompt.to_string())
+    }
 }
 
-#[derive(Builder, Clone, Debug, Default, Deserialize, Serialize)]
-pub struct Chat {
-    id: String,
-    object: String,
-    created: i64,
-    pub choices: Vec<ChatChoice>,
+impl From<Vec<String>> for Prompt {
+    fn from(prompts: Vec<String>) -> Self {
+        Self::Multiple(prompts)
+    }
+}
 
-    pub usage: TokenUsage,
+impl From<Vec<&str>> for Prompt {
+    fn from(prompts: Vec<&str>) -> Self {
+        Self::Multiple(prompts.into_iter().map(String::from).collect())
+    }
 }
 
 /// Parameters for [`Create Completion`](create) request.
@@ -275,7 +1328,7 @@ pub struct CompletionParam {
 
     /// The prompt(s) to generate completions for.
     #[serde(skip_serializing_if = "Option::is_none")]
-    prompt: Option<Vec<String>>,
+    prompt: Option<Prompt>,
 
     /// The suffix that comes after a completion of inserted text.
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -309,15 +1362,15 @@ pub struct CompletionParam {
     n: Option<u32>,
 
     // Whether to stream back partial progress.
-    #[serde(skip_serializing_if = "is_false")]
-    stream: bool,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub(crate) stream: bool,
 
     /// Include the log probabilities on the `logprobs` most likely tokens, as well the chosen tokens.
     #[serde(skip_serializing_if = "Option::is_none")]
     logprobs: Option<f32>,
 
     /// Echo back the prompt in addition to the completion
-    #[serde(skip_serializing_if = "is_false")]
+    #[serde(default, skip_serializing_if = "is_false")]
     echo: bool,
 
     /// Up to 4 sequences where the API will stop generating further tokens.
@@ -368,8 +1421,15 @@ pub struct Completion {
     pub model: String,
     pub choices: Vec<Choices>,
 
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(
+        default,
+        deserialize_with = "lenient",
+        skip_serializing_if = "Option::is_none"
+    )]
     pub usage: Option<TokenUsage>,
+
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 /// Parameters for [`Create Edit`](create) request.
@@ -415,15 +1475,22 @@ impl EditParamBuilder {
 }
 
 /// Response from [`Create Edit`](create) request.
-#[derive(Debug, Default, Deserialize)]
+#[derive(Debug, Default, Deserialize, Serialize)]
 #[serde(default)]
 pub struct Edit {
     pub object: String,
     pub created: u64,
     pub choices: Vec<Choices>,
 
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(
+        default,
+        deserialize_with = "lenient",
+        skip_serializing_if = "Option::is_none"
+    )]
     pub usage: Option<TokenUsage>,
+
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 /// Parameters for [`Create Embedding`](create) request.
@@ -461,7 +1528,15 @@ pub struct Embedding {
     pub data: Vec<EmbeddingData>,
     pub mode: String,
 
+    #[serde(
+        default,
+        deserialize_with = "lenient",
+        skip_serializing_if = "Option::is_none"
+    )]
     pub usage: Option<TokenUsage>,
+
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 /// The distance between two vectors measures their relatedness. Small distances suggest high relatedness and large distances suggest low relatedness.
@@ -470,6 +1545,9 @@ pub struct EmbeddingData {
     pub object: String,
     pub embedding: Embeddings,
     pub index: u64,
+
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 type Embeddings = Vec<f32>;
@@ -481,8 +1559,15 @@ pub struct ListFiles {
     pub data: Files,
     pub object: String,
 
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(
+        default,
+        deserialize_with = "lenient",
+        skip_serializing_if = "Option::is_none"
+    )]
     pub token_usage: Option<TokenUsage>,
+
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 /// The Possible Purposes of the uploaded documents.
@@ -592,6 +1677,7 @@ pub struct FineTune {
     pub created_at: u64,
     pub events: Events,
 
+    #[serde(deserialize_with = "lenient")]
     pub hyperparams: HyperParams,
     pub organization_id: String,
     pub result_files: Files,
@@ -600,8 +1686,15 @@ pub struct FineTune {
     pub status: String,
     pub updated_at: u64,
 
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(
+        default,
+        deserialize_with = "lenient",
+        skip_serializing_if = "Option::is_none"
+    )]
     pub token_usage: Option<TokenUsage>,
+
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 /// Hyper parameters for fine-tuning a model.
@@ -616,6 +1709,9 @@ pub struct HyperParams {
     pub classification_n_classes: u32,
     pub classification_positive_class: String,
     pub classification_betas: Vec<f32>,
+
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 /// Events occuring on Fine-tunes
@@ -626,6 +1722,9 @@ pub struct Event {
     pub created_at: u64,
     pub level: String,
     pub message: String,
+
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 type Events = Vec<Event>;
@@ -636,8 +1735,15 @@ pub struct ListEvents {
     pub object: String,
     pub data: Vec<Event>,
 
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(
+        default,
+        deserialize_with = "lenient",
+        skip_serializing_if = "Option::is_none"
+    )]
     pub token_usage: Option<TokenUsage>,
+
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 #[derive(Debug, Default, Deserialize, Serialize)]
@@ -646,8 +1752,15 @@ pub struct ListFineTune {
     pub object: String,
     pub data: Vec<FineTune>,
 
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(
+        default,
+        deserialize_with = "lenient",
+        skip_serializing_if = "Option::is_none"
+    )]
     pub token_usage: Option<TokenUsage>,
+
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 /// The size of the generated images.
@@ -726,14 +1839,29 @@ impl GenerateImageParamBuilder {
 /// Response from [Generate](generate), [Edit](edit) & [Variation](variate) requests.
 #[derive(Debug, Deserialize, Serialize)]
 pub struct Image {
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(
+        default,
+        deserialize_with = "lenient",
+        skip_serializing_if = "Option::is_none"
+    )]
     pub created: Option<u64>,
 
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(
+        default,
+        deserialize_with = "lenient",
+        skip_serializing_if = "Option::is_none"
+    )]
     pub data: Option<Links>,
 
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(
+        default,
+        deserialize_with = "lenient",
+        skip_serializing_if = "Option::is_none"
+    )]
     pub token_usage: Option<TokenUsage>,
+
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 impl Image {
@@ -747,7 +1875,7 @@ impl Image {
     /// ## Example
     /// ```no_run
     /// // Generate an image based on a prompt and save it locally.
-    /// use fieri::{Client, image::{ImageSize, GenerateImageParamBuilder, generate}};
+    /// use fieri::{Client, image::generate, types::{ImageSize, GenerateImageParamBuilder}};
     ///
     /// #[tokio::main]
     /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -776,7 +1904,7 @@ impl Image {
                 let fname = resp
                     .url()
                     .path_segments()
-                    .and_then(|segments| segments.last())
+                    .and_then(|mut segments| segments.next_back())
                     .unwrap_or(def_img_name.as_str());
 
                 let full_path = Path::new(path.as_ref()).join(fname);
@@ -794,6 +1922,9 @@ impl Image {
 #[derive(Debug, Deserialize, Serialize)]
 pub struct Link {
     pub url: String,
+
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 type Links = Vec<Link>;
@@ -869,15 +2000,24 @@ impl VariateImageParamBuilder {
 
 /// Response from [List Models](list) request.
 #[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
 pub struct Models {
     pub data: Vec<Model>,
 
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(
+        default,
+        deserialize_with = "lenient",
+        skip_serializing_if = "Option::is_none"
+    )]
     pub token_usage: Option<TokenUsage>,
+
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 /// Response from [Retrieve a Model](retrieve) request.
 #[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
 pub struct Model {
     pub id: String,
     pub object: String,
@@ -886,15 +2026,27 @@ pub struct Model {
     pub permission: Vec<Permissions>,
     pub root: String,
 
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(
+        default,
+        deserialize_with = "lenient",
+        skip_serializing_if = "Option::is_none"
+    )]
     pub parent: Option<String>,
 
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(
+        default,
+        deserialize_with = "lenient",
+        skip_serializing_if = "Option::is_none"
+    )]
     pub token_usage: Option<TokenUsage>,
+
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 /// Types of permissions that can be applied to a model.
 #[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
 pub struct Permissions {
     pub id: String,
     pub object: String,
@@ -908,8 +2060,15 @@ pub struct Permissions {
     pub organization: String,
     pub is_blocking: bool,
 
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(
+        default,
+        deserialize_with = "lenient",
+        skip_serializing_if = "Option::is_none"
+    )]
     pub group: Option<String>,
+
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 /// Parameters for [`Create Moderation`](create) request.
@@ -943,7 +2102,15 @@ pub struct Moderation {
     pub flagged: bool,
     pub results: Vec<ModerationResult>,
 
+    #[serde(
+        default,
+        deserialize_with = "lenient",
+        skip_serializing_if = "Option::is_none"
+    )]
     pub token_usage: Option<TokenUsage>,
+
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 /// The result of the content moderation request.
@@ -952,6 +2119,9 @@ pub struct Moderation {
 pub struct ModerationResult {
     pub categories: Categories,
     pub category_scores: CategoryScores,
+
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 /// Contains a per-category binary content policy violation flags.
@@ -971,6 +2141,9 @@ pub struct Categories {
     pub violence: bool,
     #[serde(rename = "violence/graphic")]
     pub violence_graphic: bool,
+
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 /// Contains a per-category raw scores output by the model, denoting the model's confidence that the input violates the OpenAI's policy for the category.
@@ -992,6 +2165,9 @@ pub struct CategoryScores {
     pub violence: f64,
     #[serde(rename = "violence/graphic")]
     pub violence_graphic: f64,
+
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 #[cfg(test)]
@@ -1045,6 +2221,459 @@ mod tests {
         assert_eq!(resp.usage.prompt_tokens, 9);
     }
 
+    /// Drops the `null`s of a payload, which aren't serialized back.
+    fn without_nulls(value: Value) -> Value {
+        match value {
+            Value::Object(object) => Value::Object(
+                object
+                    .into_iter()
+                    .filter(|(_, v)| !v.is_null())
+                    .map(|(k, v)| (k, without_nulls(v)))
+                    .collect(),
+            ),
+            Value::Array(items) => Value::Array(items.into_iter().map(without_nulls).collect()),
+            value => value,
+        }
+    }
+
+    #[test]
+    fn test_chat_round_trip() {
+        let payload: Value = serde_json::from_str(
+            r#"
+            {
+                "id": "chatcmpl-B9MBs8CjcvOU2jLn4n570S5qMJKcT",
+                "object": "chat.completion",
+                "created": 1741569952,
+                "model": "gpt-4.1-2025-04-14",
+                "choices": [{
+                    "index": 0,
+                    "message": {
+                        "role": "assistant",
+                        "content": "Hello!",
+                        "refusal": null,
+                        "annotations": []
+                    },
+                    "logprobs": {
+                        "content": [{
+                            "token": "Hello",
+                            "logprob": -0.31725305,
+                            "bytes": [72, 101, 108, 108, 111],
+                            "top_logprobs": [
+                                {"token": "Hello", "logprob": -0.31725305, "bytes": [72, 101, 108, 108, 111]},
+                                {"token": "Hi", "logprob": -1.3190403, "bytes": [72, 105]}
+                            ]
+                        }, {
+                            "token": "!",
+                            "logprob": -0.02380986,
+                            "bytes": [33],
+                            "top_logprobs": [
+                                {"token": "!", "logprob": -0.02380986, "bytes": [33]}
+                            ]
+                        }],
+                        "refusal": null
+                    },
+                    "finish_reason": "stop"
+                }],
+                "usage": {
+                    "prompt_tokens": 19,
+                    "completion_tokens": 2,
+                    "total_tokens": 21,
+                    "prompt_tokens_details": {"cached_tokens": 0, "audio_tokens": 0},
+                    "completion_tokens_details": {
+                        "reasoning_tokens": 0,
+                        "audio_tokens": 0,
+                        "accepted_prediction_tokens": 0,
+                        "rejected_prediction_tokens": 0
+                    }
+                },
+                "service_tier": "default",
+                "system_fingerprint": "fp_50cad350e4"
+            }
+            "#,
+        )
+        .unwrap();
+
+        let resp: Chat = serde_json::from_value(payload.clone()).unwrap();
+        let choice = &resp.choices[0];
+        let logprobs = choice.logprobs.as_ref().unwrap().content.as_ref().unwrap();
+
+        assert_eq!(resp.model, "gpt-4.1-2025-04-14");
+        assert_eq!(resp.system_fingerprint.as_deref(), Some("fp_50cad350e4"));
+        assert_eq!(resp.service_tier.as_deref(), Some("default"));
+        assert_eq!(resp.usage.total_tokens, 21);
+        assert_eq!(choice.message.name, None);
+        assert_eq!(choice.message.refusal, None);
+        assert_eq!(logprobs[0].token, "Hello");
+        assert_eq!(logprobs[0].top_logprobs[1].bytes, Some(vec![72, 105]));
+
+        assert_eq!(serde_json::to_value(&resp).unwrap(), without_nulls(payload));
+    }
+
+    #[test]
+    fn test_chat_message_round_trip() {
+        let payload: Value = serde_json::from_str(
+            r#"
+            [
+                {"role": "system", "content": "You are a helpful assistant."},
+                {"role": "user", "content": "Hello!", "name": "alice"},
+                {"role": "assistant", "content": "", "refusal": "I can't help with that."}
+            ]
+            "#,
+        )
+        .unwrap();
+
+        let messages: Vec<ChatMessage> = serde_json::from_value(payload.clone()).unwrap();
+
+        assert_eq!(messages[1].name.as_deref(), Some("alice"));
+        assert_eq!(
+            messages[2].refusal.as_deref(),
+            Some("I can't help with that.")
+        );
+        assert_eq!(serde_json::to_value(&messages).unwrap(), payload);
+        assert_eq!(ChatMessage::from("Hello!".to_string()).name, None);
+    }
+
+    #[test]
+    fn test_chat_unknown_and_malformed_fields() {
+        let resp: Chat = serde_json::from_str(
+            r#"
+            {
+                "id": "chatcmpl-123",
+                "object": "chat.completion",
+                "created": 1677652288,
+                "prompt_filter_results": [],
+                "choices": [{
+                  "index": 0,
+                  "message": {"role": "assistant", "content": "Hi!", "annotations": []},
+                  "finish_reason": 42
+                }],
+                "usage": "unavailable"
+              }
+            "#,
+        )
+        .unwrap();
+
+        assert!(resp.extra["prompt_filter_results"].is_array());
+        assert_eq!(resp.choices[0].message.content, "Hi!");
+        assert!(resp.choices[0].message.extra.contains_key("annotations"));
+        assert_eq!(resp.choices[0].finish_reason, None);
+        assert_eq!(resp.usage.total_tokens, 0);
+
+        let value = serde_json::to_value(&resp).unwrap();
+        assert!(value["prompt_filter_results"].is_array());
+    }
+
+    #[test]
+    fn test_chat_tool_calls_deserialization() {
+        let resp: Chat = serde_json::from_str(
+            r#"
+            {
+                "id": "chatcmpl-abc123",
+                "object": "chat.completion",
+                "created": 1699896916,
+                "choices": [{
+                  "index": 0,
+                  "message": {
+                    "role": "assistant",
+                    "content": null,
+                    "tool_calls": [{
+                      "id": "call_abc123",
+                      "type": "function",
+                      "function": {
+                        "name": "get_current_weather",
+                        "arguments": "{\"location\": \"Boston, MA\"}"
+                      }
+                    }]
+                  },
+                  "finish_reason": "tool_calls"
+                }]
+              }
+            "#,
+        )
+        .unwrap();
+
+        let message = &resp.choices[0].message;
+        let call = &message.tool_calls.as_ref().unwrap()[0];
+        let arguments: Value = call.function.arguments().unwrap();
+
+        assert_eq!(message.content, "");
+        assert_eq!(call.id, "call_abc123");
+        assert_eq!(call.kind, ToolType::Function);
+        assert_eq!(call.function.name, "get_current_weather");
+        assert_eq!(arguments["location"], "Boston, MA");
+    }
+
+    #[test]
+    fn test_chat_tools_serialization() {
+        let result = ChatMessageBuilder::new(ChatRole::Tool, r#"{"temperature": 22}"#)
+            .tool_call_id("call_abc123")
+            .build()
+            .unwrap();
+        let param = ChatParamBuilder::new("gpt-4o", vec![result])
+            .tools(vec![Tool::function(
+                "get_current_weather",
+                "Get the current weather in a given location",
+                serde_json::json!({
+                    "type": "object",
+                    "properties": {"location": {"type": "string"}},
+                    "required": ["location"],
+                }),
+            )])
+            .tool_choice(ToolChoice::Function("get_current_weather".to_string()))
+            .parallel_tool_calls(false)
+            .build()
+            .unwrap();
+
+        let value = serde_json::to_value(&param).unwrap();
+
+        assert_eq!(value["messages"][0]["role"], "tool");
+        assert_eq!(value["messages"][0]["tool_call_id"], "call_abc123");
+        assert!(value["messages"][0].get("tool_calls").is_none());
+        assert_eq!(value["tools"][0]["type"], "function");
+        assert_eq!(value["tools"][0]["function"]["name"], "get_current_weather");
+        assert!(value["tools"][0]["function"].get("strict").is_none());
+        assert_eq!(
+            value["tool_choice"]["function"]["name"],
+            "get_current_weather"
+        );
+        assert_eq!(value["parallel_tool_calls"], false);
+
+        for (choice, json) in [
+            (ToolChoice::None, r#""none""#),
+            (ToolChoice::Auto, r#""auto""#),
+            (ToolChoice::Required, r#""required""#),
+            (
+                ToolChoice::Function("f".to_string()),
+                r#"{"function":{"name":"f"},"type":"function"}"#,
+            ),
+        ] {
+            assert_eq!(serde_json::to_string(&choice).unwrap(), json);
+            assert_eq!(serde_json::from_str::<ToolChoice>(json).unwrap(), choice);
+        }
+    }
+
+    #[test]
+    fn test_chat_content_parts() {
+        let png = [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+        let image = ImageUrl::from_bytes(png).unwrap().detail(ImageDetail::Low);
+        let message = ChatMessageBuilder::new(
+            "user",
+            vec![ContentPart::text("What's in this image?"), image.into()],
+        )
+        .build()
+        .unwrap();
+
+        let value = serde_json::to_value(&message).unwrap();
+        assert_eq!(
+            value["content"],
+            serde_json::json!([
+                {"type": "text", "text": "What's in this image?"},
+                {"type": "image_url", "image_url": {"url": "data:image/png;base64,iVBORw0KGgo=", "detail": "low"}},
+            ])
+        );
+
+        let message: ChatMessage = serde_json::from_value(value).unwrap();
+        assert_eq!(message.content.to_text(), "What's in this image?");
+        assert_eq!(message.content.as_text(), None);
+
+        let message: ChatMessage =
+            serde_json::from_str(r#"{"role": "user", "content": "Hello!"}"#).unwrap();
+        assert_eq!(message.content, "Hello!");
+    }
+
+    #[test]
+    fn test_chat_content_unknown_parts() {
+        let value = serde_json::json!({
+            "role": "user",
+            "content": [
+                {"type": "text", "text": "Summarize this file."},
+                {"type": "file", "file": {"file_id": "file-abc123"}},
+            ],
+        });
+
+        let message: ChatMessage = serde_json::from_value(value.clone()).unwrap();
+        let ChatContent::Parts(parts) = &message.content else {
+            panic!("Expected parts, got {:?}", message.content);
+        };
+        assert_eq!(parts[0], ContentPart::text("Summarize this file."));
+        assert!(
+            matches!(&parts[1], ContentPart::Unknown(part) if part["file"]["file_id"] == "file-abc123")
+        );
+        assert_eq!(serde_json::to_value(&message).unwrap(), value);
+
+        let message: ChatMessage =
+            serde_json::from_str(r#"{"role": "assistant", "content": null}"#).unwrap();
+        assert!(message.content.is_empty());
+    }
+
+    #[test]
+    fn test_media_validation() {
+        let audio = InputAudio::from_bytes(b"ID3\x04\x00").unwrap();
+        assert_eq!(audio.format, AudioFormat::Mp3);
+        assert_eq!(
+            serde_json::to_value(ContentPart::from(audio)).unwrap()["input_audio"]["format"],
+            "mp3"
+        );
+
+        assert!(matches!(
+            ImageUrl::from_bytes(b"%PDF-1.7"),
+            Err(crate::Error::UnsupportedMedia(_))
+        ));
+
+        let path = std::env::temp_dir().join("fieri-test-too-large.png");
+        fs::File::create(&path)
+            .unwrap()
+            .set_len(MAX_IMAGE_SIZE + 1)
+            .unwrap();
+        let result = ImageUrl::from_path(&path);
+        fs::remove_file(&path).unwrap();
+
+        assert!(matches!(
+            result,
+            Err(crate::Error::MediaTooLarge {
+                max: MAX_IMAGE_SIZE,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_response_format_serialization() {
+        let format = serde_json::to_value(ResponseFormat::JsonObject).unwrap();
+        assert_eq!(format, serde_json::json!({"type": "json_object"}));
+
+        let format = ResponseFormat::json_schema("answer", serde_json::json!({"type": "object"}));
+        assert_eq!(
+            serde_json::to_value(format).unwrap(),
+            serde_json::json!({
+                "type": "json_schema",
+                "json_schema": {"name": "answer", "schema": {"type": "object"}, "strict": true},
+            })
+        );
+    }
+
+    #[cfg(feature = "schema")]
+    #[test]
+    fn test_response_format_json_schema_for() {
+        #[derive(schemars::JsonSchema)]
+        #[allow(dead_code)]
+        struct Step {
+            explanation: String,
+            count: u32,
+            note: Option<String>,
+        }
+
+        #[derive(schemars::JsonSchema)]
+        #[allow(dead_code)]
+        struct Steps {
+            steps: Vec<Step>,
+        }
+
+        let ResponseFormat::JsonSchema { json_schema } = ResponseFormat::json_schema_for::<Steps>()
+        else {
+            panic!("expected a JSON schema format");
+        };
+        let schema = json_schema.schema;
+
+        assert_eq!(json_schema.name, "Steps");
+        assert!(schema.get("$schema").is_none());
+        assert_eq!(schema["additionalProperties"], false);
+
+        let step = &schema["definitions"]["Step"];
+        assert_eq!(step["additionalProperties"], false);
+        assert_eq!(
+            step["required"],
+            serde_json::json!(["count", "explanation", "note"])
+        );
+        assert!(step["properties"]["count"].get("format").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_chat_stream_tool_calls_accumulation() {
+        let chunks: Vec<ChatChunk> = [
+            r#"{"id":"chatcmpl-123","object":"chat.completion.chunk","created":1694268190,"model":"gpt-4o","choices":[{"index":0,"delta":{"role":"assistant","content":null,"tool_calls":[{"index":0,"id":"call_1","type":"function","function":{"name":"get_weather","arguments":""}}]},"finish_reason":null}]}"#,
+            r#"{"id":"chatcmpl-123","object":"chat.completion.chunk","created":1694268190,"model":"gpt-4o","choices":[{"index":0,"delta":{"tool_calls":[{"index":0,"function":{"arguments":"{\"location\": "}}]},"finish_reason":null}]}"#,
+            r#"{"id":"chatcmpl-123","object":"chat.completion.chunk","created":1694268190,"model":"gpt-4o","choices":[{"index":0,"delta":{"tool_calls":[{"index":1,"id":"call_2","type":"function","function":{"name":"get_time","arguments":"{}"}}]},"finish_reason":null}]}"#,
+            r#"{"id":"chatcmpl-123","object":"chat.completion.chunk","created":1694268190,"model":"gpt-4o","choices":[{"index":0,"delta":{"tool_calls":[{"index":0,"function":{"arguments":"\"Paris\"}"}}]},"finish_reason":"tool_calls"}]}"#,
+        ]
+        .iter()
+        .map(|c| serde_json::from_str(c).unwrap())
+        .collect();
+
+        let stream = futures::stream::iter(chunks.into_iter().map(Ok));
+        let resp = ChatStreamAccumulator::collect(stream).await.unwrap();
+
+        let calls = resp.choices[0].message.tool_calls.as_ref().unwrap();
+        assert_eq!(calls.len(), 2);
+        assert_eq!(calls[0].id, "call_1");
+        assert_eq!(calls[0].function.name, "get_weather");
+        assert_eq!(calls[0].function.arguments, r#"{"location": "Paris"}"#);
+        assert_eq!(calls[1].id, "call_2");
+        assert_eq!(calls[1].function.arguments, "{}");
+        assert_eq!(resp.choices[0].message.content, "");
+        assert_eq!(
+            resp.choices[0].finish_reason,
+            Some("tool_calls".to_string())
+        );
+    }
+
+    #[test]
+    fn test_chat_stream_sparse_tool_call_indexes() {
+        let mut acc = ChatStreamAccumulator::new();
+        for index in [u32::MAX, 7] {
+            let chunk = serde_json::json!({
+                "id": "chatcmpl-123",
+                "object": "chat.completion.chunk",
+                "created": 1694268190,
+                "model": "gpt-4o",
+                "choices": [{"index": 0, "delta": {"tool_calls": [{
+                    "index": index,
+                    "id": format!("call_{index}"),
+                    "type": "function",
+                    "function": {"name": "noop", "arguments": "{}"},
+                }]}, "finish_reason": null}],
+            });
+            acc.push(&serde_json::from_value(chunk).unwrap());
+        }
+
+        let chat = acc.finish();
+        let ids: Vec<_> = chat.choices[0]
+            .message
+            .tool_calls
+            .iter()
+            .flatten()
+            .map(|c| c.id.as_str())
+            .collect();
+        assert_eq!(ids, ["call_7", "call_4294967295"]);
+    }
+
+    #[tokio::test]
+    async fn test_chat_stream_accumulation() {
+        let chunks: Vec<ChatChunk> = [
+            r#"{"id":"chatcmpl-123","object":"chat.completion.chunk","created":1694268190,"model":"gpt-3.5-turbo","choices":[{"index":0,"delta":{"role":"assistant","content":""},"finish_reason":null},{"index":1,"delta":{"role":"assistant","content":""},"finish_reason":null}]}"#,
+            r#"{"id":"chatcmpl-123","object":"chat.completion.chunk","created":1694268190,"model":"gpt-3.5-turbo","choices":[{"index":1,"delta":{"content":"Hi"},"finish_reason":null}]}"#,
+            r#"{"id":"chatcmpl-123","object":"chat.completion.chunk","created":1694268190,"model":"gpt-3.5-turbo","choices":[{"index":0,"delta":{"content":"Hello"},"finish_reason":null}]}"#,
+            r#"{"id":"chatcmpl-123","object":"chat.completion.chunk","created":1694268190,"model":"gpt-3.5-turbo","choices":[{"index":0,"delta":{"content":" there!"},"finish_reason":"stop"},{"index":1,"delta":{},"finish_reason":"length"}]}"#,
+            r#"{"id":"chatcmpl-123","object":"chat.completion.chunk","created":1694268190,"model":"gpt-3.5-turbo","choices":[],"usage":{"prompt_tokens":9,"completion_tokens":4,"total_tokens":13}}"#,
+        ]
+        .iter()
+        .map(|c| serde_json::from_str(c).unwrap())
+        .collect();
+
+        let stream = futures::stream::iter(chunks.into_iter().map(Ok));
+        let resp = ChatStreamAccumulator::collect(stream).await.unwrap();
+
+        assert_eq!(resp.id, "chatcmpl-123");
+        assert_eq!(resp.object, "chat.completion");
+        assert_eq!(resp.choices.len(), 2);
+        assert_eq!(resp.choices[0].message.role, ChatRole::Assistant);
+        assert_eq!(resp.choices[0].message.content, "Hello there!");
+        assert_eq!(resp.choices[0].finish_reason, Some("stop".to_string()));
+        assert_eq!(resp.choices[1].message.content, "Hi");
+        assert_eq!(resp.choices[1].finish_reason, Some("length".to_string()));
+        assert_eq!(resp.usage.total_tokens, 13);
+    }
+
     #[test]
     fn test_create_completion_deserialization() {
         let param: CompletionParam = serde_json::from_str(
@@ -1090,17 +2719,80 @@ mod tests {
         .unwrap();
 
         assert_eq!(param.model, "text-davinci-003");
-        assert_eq!(param.prompt.unwrap(), "Say this is a test");
+        assert_eq!(param.prompt, Some("Say this is a test".into()));
         assert_eq!(param.suffix, None);
         assert_eq!(resp.choices.len(), 1);
         assert_eq!(
             resp.choices[0].text,
             Some("\n\nThis is indeed a test".to_string())
         );
-        assert_eq!(resp.choices[0].logprobs, None);
+        assert!(resp.choices[0].logprobs.is_none());
         assert_eq!(resp.usage.unwrap().prompt_tokens, 5);
     }
 
+    #[test]
+    fn test_completion_param_prompts() {
+        let single = CompletionParamBuilder::default()
+            .model("text-davinci-003")
+            .prompt("Say this is a test")
+            .build()
+            .unwrap();
+        let multiple = CompletionParamBuilder::default()
+            .model("text-davinci-003")
+            .prompt(vec!["Say this is a test", "Say this is another test"])
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            serde_json::to_value(single).unwrap()["prompt"],
+            "Say this is a test"
+        );
+        assert_eq!(
+            serde_json::to_value(&multiple).unwrap()["prompt"],
+            serde_json::json!(["Say this is a test", "Say this is another test"])
+        );
+
+        let param: CompletionParam =
+            serde_json::from_str(r#"{"model": "text-davinci-003", "prompt": ["a", "b"]}"#).unwrap();
+        assert_eq!(
+            param.prompt,
+            Some(Prompt::Multiple(vec!["a".to_string(), "b".to_string()]))
+        );
+    }
+
+    #[test]
+    fn test_completion_logprobs_deserialization() {
+        let resp: Completion = serde_json::from_str(
+            r#"
+            {
+                "id": "cmpl-uqkvlQyYK7bGYrRHQ0eXlWi7",
+                "object": "text_completion",
+                "created": 1589478378,
+                "model": "text-davinci-003",
+                "choices": [
+                {
+                    "text": " test",
+                    "index": 0,
+                    "logprobs": {
+                        "tokens": [" test"],
+                        "token_logprobs": [-0.25],
+                        "top_logprobs": [{" test": -0.25, " trial": -1.5}],
+                        "text_offset": [18]
+                    },
+                    "finish_reason": "length"
+                }
+                ]
+            }
+            "#,
+        )
+        .unwrap();
+
+        let logprobs = resp.choices[0].logprobs.as_ref().unwrap();
+        assert_eq!(logprobs.tokens, [" test"]);
+        assert_eq!(logprobs.token_logprobs, [Some(-0.25)]);
+        assert_eq!(logprobs.top_logprobs[0].as_ref().unwrap()[" trial"], -1.5);
+    }
+
     #[test]
     fn test_create_edit_deserialization() {
         let param: EditParam = serde_json::from_str(
@@ -1,4 +1,31 @@
+use serde::{de::DeserializeOwned, Deserialize, Deserializer};
+use serde_json::Value;
+
 // https://stackoverflow.com/questions/74726116/how-to-skip-serde-serialization-with-skip-serializing-if-for-a-boolean-field
 pub(crate) fn is_false(b: &bool) -> bool {
     !(*b)
 }
+
+/// Deserializes a field, falling back to its default when malformed, so a single unexpected value
+/// doesn't fail the whole response.
+pub(crate) fn lenient<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: DeserializeOwned + Default,
+{
+    let value = Value::deserialize(deserializer)?;
+
+    Ok(T::deserialize(value).unwrap_or_else(|err| {
+        log::debug!("Ignoring malformed field: {err}");
+        T::default()
+    }))
+}
+
+/// Deserializes a field, falling back to its default when `null`.
+pub(crate) fn null_as_default<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de> + Default,
+{
+    Ok(Option::<T>::deserialize(deserializer)?.unwrap_or_default())
+}